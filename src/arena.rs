@@ -0,0 +1,570 @@
+// Runs tournaments between built-in and external engines without the interface,
+// playing every game on the library's App like the interface does
+use std::{
+    collections::VecDeque,
+    env,
+    path::Path,
+    process,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use tictactoe_library::{
+    app::{App, AppState},
+    game::{GameState, Player},
+};
+use tictactoe_tui::{
+    autopilot,
+    board::{Board, Mark},
+    bots::{Aggressive, Defensive, Mirror, Perfect, Random, Trickster},
+    engine::{split_command, Engine},
+    learning::{self, Learned},
+    mcts::Mcts,
+    notation::Outcome,
+    opponent::Opponent,
+};
+
+const USAGE: &str = "\
+usage: tic-tac-toe-arena [options] <engine> <engine>...
+
+Engines are random, perfect, aggressive, defensive, trickster, mirror, learned or
+learned:<table> (learned-3x3.txt, saved by tic-tac-toe train, by default), mcts or
+mcts:<playouts> (20000 playouts by default) or the command line of an external engine.
+
+options:
+    --gauntlet          play the first engine against each of the others
+                        instead of everyone against everyone
+    --games <n>         games per pairing, alternating who starts (default 10)
+    --threads <n>       games to run at the same time (default: one per core)
+    --time <ms>         thinking time per move (default 1000)
+    --format <format>   table, csv or json (default table)";
+
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+struct Options {
+    engines: Vec<String>,
+    gauntlet: bool,
+    games: usize,
+    threads: usize,
+    budget: Duration,
+    format: Format,
+}
+
+impl Options {
+    fn from_args() -> Result<Options, String> {
+        let mut options = Options {
+            engines: Vec::new(),
+            gauntlet: false,
+            games: 10,
+            threads: thread::available_parallelism().map_or(4, |n| n.get()),
+            budget: Duration::from_secs(1),
+            format: Format::Table,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--gauntlet" => options.gauntlet = true,
+                "--games" => options.games = number(&value()?)?,
+                "--threads" => options.threads = number(&value()?)?.max(1),
+                "--time" => options.budget = Duration::from_millis(number(&value()?)? as u64),
+                "--format" => {
+                    options.format = match value()?.as_str() {
+                        "table" => Format::Table,
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        format => return Err(format!("unknown format: {}", format)),
+                    }
+                }
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown argument: {}", arg)),
+                _ => options.engines.push(arg),
+            }
+        }
+        if options.engines.len() < 2 {
+            return Err(USAGE.to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn number(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a number, got {:?}", value))
+}
+
+// Built-in engines that can be given a setting after a colon, like learned:<table>
+fn setting(engine: &str) -> (&str, Option<&str>) {
+    match engine.split_once(':') {
+        Some((name @ ("learned" | "mcts"), setting)) => (name, Some(setting)),
+        _ => (engine, None),
+    }
+}
+
+fn create(engine: &str) -> Result<Box<dyn Opponent>, String> {
+    match setting(engine) {
+        ("random", None) => Ok(Box::new(Random::new())),
+        ("perfect", None) => Ok(Box::new(Perfect::new())),
+        ("aggressive", None) => Ok(Box::new(Aggressive::new())),
+        ("defensive", None) => Ok(Box::new(Defensive::new())),
+        ("trickster", None) => Ok(Box::new(Trickster::new())),
+        ("mirror", None) => Ok(Box::new(Mirror::new())),
+        ("learned", table) => {
+            let table = table.map_or_else(|| learning::default_path(3), str::to_string);
+            Ok(Box::new(Learned::load(&table)?))
+        }
+        ("mcts", playouts) => {
+            let playouts = playouts.map_or(Ok(20000), number)?.max(1);
+            // games already run on a thread each, so the search keeps to its own
+            Ok(Box::new(Mcts::new(playouts).threads(1)))
+        }
+        _ => Ok(Box::new(Engine::spawn(engine)?)),
+    }
+}
+
+// Short unique names for the crosstable
+fn names(engines: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for engine in engines {
+        let stem = |path: &str| {
+            Path::new(path).file_stem().map_or_else(
+                || path.to_string(),
+                |stem| stem.to_string_lossy().to_string(),
+            )
+        };
+        let name = match setting(engine) {
+            (name, Some(setting)) => format!("{}:{}", name, stem(setting)),
+            _ => {
+                let words = split_command(engine).unwrap_or_default();
+                stem(words.first().map_or(engine.as_str(), String::as_str))
+            }
+        };
+        let taken = names
+            .iter()
+            .filter(|other| other.split('#').next() == Some(&name));
+        let name = match taken.count() {
+            0 => name,
+            n => format!("{}#{}", name, n + 1),
+        };
+        names.push(name);
+    }
+    names
+}
+
+// Plays one game. A side that fails to answer or plays a taken cell loses it.
+fn play(
+    x: &mut Box<dyn Opponent>,
+    o: &mut Box<dyn Opponent>,
+    budget: Duration,
+) -> Result<(Outcome, Option<String>), String> {
+    let mut app = App::new();
+    autopilot::start_game(&mut app)?;
+    loop {
+        let (board, mark) = match &app.state {
+            AppState::Playing(GameState::GameOver(winner, _)) => {
+                let outcome = match winner {
+                    Some(Player::Player1) => Outcome::Win(Mark::X),
+                    Some(Player::Player2) => Outcome::Win(Mark::O),
+                    None => Outcome::Draw,
+                };
+                return Ok((outcome, None));
+            }
+            AppState::Playing(GameState::GameInProgress(cells, player, _)) => {
+                (Board::from_cells(cells), Mark::of(*player))
+            }
+            _ => return Err("The game ended unexpectedly".to_string()),
+        };
+        let side = match mark {
+            Mark::X => &mut *x,
+            Mark::O => &mut *o,
+        };
+        let answer = side
+            .choose(&board, budget)
+            .and_then(|cell| match board.get(cell) {
+                None => Ok(cell),
+                Some(_) => Err(format!("{} played on a taken cell", side.name())),
+            });
+        match answer {
+            Ok(cell) => autopilot::place(&mut app, cell)?,
+            Err(error) => return Ok((Outcome::Win(mark.other()), Some(error))),
+        }
+    }
+}
+
+struct Report {
+    x: usize,
+    o: usize,
+    outcome: Outcome,
+    error: Option<String>,
+}
+
+fn run(options: &Options) -> Result<Vec<Report>, String> {
+    let count = options.engines.len();
+    let pairings: Vec<(usize, usize)> = if options.gauntlet {
+        (1..count).map(|other| (0, other)).collect()
+    } else {
+        (0..count)
+            .flat_map(|a| (a + 1..count).map(move |b| (a, b)))
+            .collect()
+    };
+    let jobs: VecDeque<(usize, usize)> = pairings
+        .iter()
+        .flat_map(|&(a, b)| {
+            (0..options.games).map(move |game| if game % 2 == 0 { (a, b) } else { (b, a) })
+        })
+        .collect();
+    let total = jobs.len();
+    let jobs = Arc::new(Mutex::new(jobs));
+
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..options.threads.min(total) {
+        let jobs = Arc::clone(&jobs);
+        let tx = tx.clone();
+        let engines = options.engines.clone();
+        let budget = options.budget;
+        thread::spawn(move || loop {
+            let job = jobs.lock().ok().and_then(|mut jobs| jobs.pop_front());
+            let (x, o) = match job {
+                Some(job) => job,
+                None => return,
+            };
+            let report = create(&engines[x]).and_then(|mut first| {
+                let mut second = create(&engines[o])?;
+                let (outcome, error) = play(&mut first, &mut second, budget)?;
+                Ok(Report {
+                    x,
+                    o,
+                    outcome,
+                    error,
+                })
+            });
+            if tx.send(report).is_err() {
+                return;
+            }
+        });
+    }
+    drop(tx);
+
+    let names = names(&options.engines);
+    let mut reports = Vec::new();
+    for report in rx {
+        let report = report?;
+        eprintln!(
+            "game {}/{}: {} - {} {}{}",
+            reports.len() + 1,
+            total,
+            names[report.x],
+            names[report.o],
+            report.outcome,
+            report
+                .error
+                .as_ref()
+                .map_or_else(String::new, |error| format!(" ({})", error))
+        );
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+#[derive(Clone, Copy, Default)]
+struct Record {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl Record {
+    fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    // Rating difference to the average opponent implied by the score
+    fn elo(&self) -> Option<f64> {
+        let games = self.games() as f64;
+        if games == 0.0 {
+            return None;
+        }
+        // keep perfect and zero scores finite
+        let score = (self.score() / games).clamp(0.5 / games, 1.0 - 0.5 / games);
+        Some(-400.0 * (1.0 / score - 1.0).log10())
+    }
+
+    fn add(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+// results[a][b] is how a did against b
+fn crosstable(count: usize, reports: &[Report]) -> Vec<Vec<Record>> {
+    let mut results = vec![vec![Record::default(); count]; count];
+    for report in reports {
+        let (x, o) = (report.x, report.o);
+        match report.outcome {
+            Outcome::Win(Mark::X) => {
+                results[x][o].wins += 1;
+                results[o][x].losses += 1;
+            }
+            Outcome::Win(Mark::O) => {
+                results[o][x].wins += 1;
+                results[x][o].losses += 1;
+            }
+            Outcome::Draw | Outcome::Unfinished => {
+                results[x][o].draws += 1;
+                results[o][x].draws += 1;
+            }
+        }
+    }
+    results
+}
+
+fn total(results: &[Record]) -> Record {
+    let mut total = Record::default();
+    for record in results {
+        total.add(*record);
+    }
+    total
+}
+
+fn elo_text(record: &Record) -> String {
+    record
+        .elo()
+        .map_or_else(|| "-".to_string(), |elo| format!("{:+.0}", elo))
+}
+
+fn print_table(names: &[String], results: &[Vec<Record>]) {
+    let cell = |record: &Record| {
+        if record.games() == 0 {
+            "-".to_string()
+        } else {
+            format!("{}-{}-{}", record.wins, record.draws, record.losses)
+        }
+    };
+    let mut rows = vec![{
+        let mut header = vec!["Engine".to_string()];
+        header.extend(names.iter().cloned());
+        header.extend(["Score".to_string(), "Elo".to_string()]);
+        header
+    }];
+    for (name, row) in names.iter().zip(results) {
+        let total = total(row);
+        let mut line = vec![name.clone()];
+        line.extend(row.iter().map(cell));
+        line.push(format!("{}/{}", total.score(), total.games()));
+        line.push(elo_text(&total));
+        rows.push(line);
+    }
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(text, width)| format!("{:<width$}", text, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+    println!("\nCells are wins-draws-losses of the row engine against the column engine.");
+    println!("Elo is relative to the average opponent each engine played.");
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn print_csv(names: &[String], results: &[Vec<Record>]) {
+    println!("engine,opponent,games,wins,draws,losses,score,elo");
+    for (name, row) in names.iter().zip(results) {
+        let total = total(row);
+        let opponents = names
+            .iter()
+            .zip(row)
+            .filter(|(_, record)| record.games() > 0);
+        for (opponent, record) in opponents.chain([(&"total".to_string(), &total)]) {
+            println!(
+                "{},{},{},{},{},{},{},{}",
+                csv_field(name),
+                csv_field(opponent),
+                record.games(),
+                record.wins,
+                record.draws,
+                record.losses,
+                record.score(),
+                record
+                    .elo()
+                    .map_or_else(String::new, |elo| format!("{:.0}", elo))
+            );
+        }
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_record(record: &Record) -> String {
+    format!(
+        "\"games\": {}, \"wins\": {}, \"draws\": {}, \"losses\": {}, \"score\": {}",
+        record.games(),
+        record.wins,
+        record.draws,
+        record.losses,
+        record.score()
+    )
+}
+
+fn print_json(names: &[String], results: &[Vec<Record>]) {
+    let engines: Vec<String> = names
+        .iter()
+        .zip(results)
+        .map(|(name, row)| {
+            let total = total(row);
+            let opponents: Vec<String> = names
+                .iter()
+                .zip(row)
+                .filter(|(_, record)| record.games() > 0)
+                .map(|(opponent, record)| {
+                    format!(
+                        "{{\"opponent\": {}, {}}}",
+                        json_string(opponent),
+                        json_record(record)
+                    )
+                })
+                .collect();
+            format!(
+                "    {{\"name\": {}, {}, \"elo\": {}, \"results\": [{}]}}",
+                json_string(name),
+                json_record(&total),
+                total
+                    .elo()
+                    .map_or_else(|| "null".to_string(), |elo| format!("{:.0}", elo)),
+                opponents.join(", ")
+            )
+        })
+        .collect();
+    println!("{{\n  \"engines\": [\n{}\n  ]\n}}", engines.join(",\n"));
+}
+
+fn main() {
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+    let reports = match run(&options) {
+        Ok(reports) => reports,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    };
+    let names = names(&options.engines);
+    let results = crosstable(options.engines.len(), &reports);
+    match options.format {
+        Format::Table => print_table(&names, &results),
+        Format::Csv => print_csv(&names, &results),
+        Format::Json => print_json(&names, &results),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(wins: usize, draws: usize, losses: usize) -> Record {
+        Record {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn names_are_short_and_unique() {
+        let engines = [
+            "random",
+            "random",
+            "'engines/my engine.exe' --depth 3",
+            "/usr/bin/my engine",
+            "learned:tables/learned-3x3.txt",
+            "mcts:500",
+            "random",
+        ];
+        let engines: Vec<String> = engines.iter().map(|engine| engine.to_string()).collect();
+        assert_eq!(
+            names(&engines),
+            [
+                "random",
+                "random#2",
+                "my engine",
+                "my",
+                "learned:learned-3x3",
+                "mcts:500",
+                "random#3"
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_follow_a_colon() {
+        assert_eq!(
+            setting("learned:my table.txt"),
+            ("learned", Some("my table.txt"))
+        );
+        assert_eq!(setting("mcts:500"), ("mcts", Some("500")));
+        assert_eq!(setting("learned"), ("learned", None));
+        assert_eq!(
+            setting(r"C:\engines\engine.exe"),
+            (r"C:\engines\engine.exe", None)
+        );
+        assert!(create("learned:/nonexistent/table.txt").is_err());
+        assert!(create("mcts:many").is_err());
+        assert!(create("mcts:500").is_ok());
+    }
+
+    #[test]
+    fn elo_follows_the_score() {
+        assert_eq!(record(0, 0, 0).elo(), None);
+        assert_eq!(record(5, 0, 5).elo(), Some(0.0));
+        assert_eq!(record(0, 10, 0).elo(), Some(0.0));
+        // three quarters of the points is about 191 points stronger
+        let elo = record(3, 0, 1).elo().unwrap();
+        assert!((elo - 190.8).abs() < 0.1, "{}", elo);
+        assert!((record(1, 0, 3).elo().unwrap() + elo).abs() < 1e-9);
+        // a perfect score is kept finite, as if half a game had been lost
+        let perfect = record(10, 0, 0).elo().unwrap();
+        assert!(perfect.is_finite() && perfect > 400.0);
+        assert!((record(0, 0, 10).elo().unwrap() + perfect).abs() < 1e-9);
+    }
+}
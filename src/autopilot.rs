@@ -0,0 +1,129 @@
+// Drives the library App with the same key presses a player would make, for
+// moves that don't come from the keyboard
+use tictactoe_library::{
+    app::{App, AppState},
+    game::GameState,
+    update::Key,
+};
+
+use crate::{
+    board::{Board, Mark},
+    notation::Game,
+};
+
+// more than enough to cross the board or the start menu
+const MAX_KEYS: usize = 16;
+
+// Starts a game between two humans from the start menu
+pub fn start_game(app: &mut App) -> Result<(), String> {
+    for _ in 0..MAX_KEYS {
+        match &app.state {
+            AppState::StartMenu(0) => {
+                app.update(Key::Enter);
+                return match &app.state {
+                    AppState::Playing(_) => Ok(()),
+                    _ => Err("Could not start a new game".to_string()),
+                };
+            }
+            AppState::StartMenu(_) => app.update(Key::Up),
+            _ => return Err("A new game can only be started from the start menu".to_string()),
+        }
+    }
+    Err("Could not reach the first start menu entry".to_string())
+}
+
+// Starts a game between two humans from wherever the app is, resetting the board of a
+// game in progress or left in the game menu
+pub fn new_game(app: &mut App) -> Result<(), String> {
+    for _ in 0..MAX_KEYS {
+        match &app.state {
+            AppState::StartMenu(_) => return start_game(app),
+            AppState::Playing(_) => {
+                app.update(Key::Char('r'));
+                return match (&app.state, Board::of(app)) {
+                    (AppState::Playing(GameState::GameInProgress(..)), Some(board))
+                        if board == Board::default() =>
+                    {
+                        Ok(())
+                    }
+                    _ => Err("Could not reset the board".to_string()),
+                };
+            }
+            // resume the game to reset it
+            AppState::GameMenu(0) => app.update(Key::Enter),
+            AppState::GameMenu(_) => app.update(Key::Up),
+            _ => return Err("The game has been closed".to_string()),
+        }
+    }
+    Err("Could not get back to the game".to_string())
+}
+
+// Moves the cursor to the cell and places the current player's piece there
+pub fn place(app: &mut App, (row, col): (usize, usize)) -> Result<(), String> {
+    for _ in 0..MAX_KEYS {
+        let (i, j) = match &app.state {
+            AppState::Playing(GameState::GameInProgress(_, _, pos)) => pos.to_tuple(),
+            _ => return Err("There is no game in progress".to_string()),
+        };
+        let key = if i > row {
+            Key::Up
+        } else if i < row {
+            Key::Down
+        } else if j > col {
+            Key::Left
+        } else if j < col {
+            Key::Right
+        } else {
+            let before = Board::of(app);
+            app.update(Key::Char('p'));
+            return match Board::of(app) {
+                Some(after) if Some(after) != before && after.get((row, col)).is_some() => Ok(()),
+                _ => Err(format!(
+                    "Could not place a piece on row {}, column {}",
+                    row + 1,
+                    col + 1
+                )),
+            };
+        };
+        app.update(key);
+    }
+    Err(format!(
+        "Could not move the cursor to row {}, column {}",
+        row + 1,
+        col + 1
+    ))
+}
+
+// Starts a new game and plays the pieces of a valid position in turn, going back to the
+// start menu if any of them can't be placed
+pub fn set_up(app: &mut App, position: &Board) -> Result<(), String> {
+    position.validate()?;
+    start_game(app)?;
+    let crosses = position.cells().filter(|(_, mark)| *mark == Some(Mark::X));
+    let mut circles = position.cells().filter(|(_, mark)| *mark == Some(Mark::O));
+    for (cross, _) in crosses {
+        let placed = place(app, cross).and_then(|()| match circles.next() {
+            Some((circle, _)) => place(app, circle),
+            None => Ok(()),
+        });
+        if let Err(error) = placed {
+            // a half built game would keep every later start from the menu failing
+            app.state = AppState::StartMenu(0);
+            app.prev_state = None;
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+// Sets up the starting position of a game and replays its moves, calling back after each one
+pub fn replay(app: &mut App, game: &Game, mut after_move: impl FnMut(&App)) -> Result<(), String> {
+    game.boards()?;
+    set_up(app, &game.start)?;
+    after_move(app);
+    for &cell in &game.moves {
+        place(app, cell)?;
+        after_move(app);
+    }
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use std::{fmt, str::FromStr};
+
+use tictactoe_library::{
+    app::{App, AppState},
+    game::{Cells, GameCell, GameState, Player},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mark {
+    X,
+    O,
+}
+
+impl Mark {
+    // Player 1 always plays crosses and moves first
+    pub fn of(player: Player) -> Mark {
+        match player {
+            Player::Player1 => Mark::X,
+            Player::Player2 => Mark::O,
+        }
+    }
+
+    pub fn player(self) -> Player {
+        match self {
+            Mark::X => Player::Player1,
+            Mark::O => Player::Player2,
+        }
+    }
+
+    pub fn other(self) -> Mark {
+        match self {
+            Mark::X => Mark::O,
+            Mark::O => Mark::X,
+        }
+    }
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mark::X => write!(f, "X"),
+            Mark::O => write!(f, "O"),
+        }
+    }
+}
+
+// The game being played, or the one paused behind the game menu
+pub fn game_state(app: &App) -> Option<&GameState> {
+    match &app.state {
+        AppState::Playing(game_state) => Some(game_state),
+        AppState::GameMenu(_) => app.prev_state.as_ref(),
+        _ => None,
+    }
+}
+
+pub fn game_cell(mark: Option<Mark>) -> GameCell {
+    match mark {
+        None => GameCell::Empty,
+        Some(Mark::X) => GameCell::Cross,
+        Some(Mark::O) => GameCell::Circle,
+    }
+}
+
+// The rules below work on square boards of any size with lines as long as a side, so the
+// learned computer can train on 4x4 boards with the same ones the 3x3 game uses
+
+// The rows, columns and both diagonals
+pub fn lines(size: usize) -> Vec<Vec<(usize, usize)>> {
+    let mut lines = Vec::new();
+    for i in 0..size {
+        lines.push((0..size).map(|j| (i, j)).collect());
+        lines.push((0..size).map(|j| (j, i)).collect());
+    }
+    lines.push((0..size).map(|i| (i, i)).collect());
+    lines.push((0..size).map(|i| (i, size - 1 - i)).collect());
+    lines
+}
+
+// The mark filling a whole line, looking up cells with get
+pub fn winner(size: usize, get: impl Fn((usize, usize)) -> Option<Mark>) -> Option<Mark> {
+    lines(size).into_iter().find_map(|line| {
+        let mark = get(line[0])?;
+        line.iter()
+            .all(|&cell| get(cell) == Some(mark))
+            .then_some(mark)
+    })
+}
+
+// The empty cells that would complete a line for the mark
+pub fn threats(
+    size: usize,
+    mark: Mark,
+    get: impl Fn((usize, usize)) -> Option<Mark>,
+) -> Vec<(usize, usize)> {
+    let mut threats = Vec::new();
+    for line in lines(size) {
+        let empty: Vec<_> = line.iter().filter(|&&cell| get(cell).is_none()).collect();
+        let marked = line.iter().filter(|&&cell| get(cell) == Some(mark)).count();
+        if let ([&cell], true) = (empty.as_slice(), marked == size - 1) {
+            if !threats.contains(&cell) {
+                threats.push(cell);
+            }
+        }
+    }
+    threats
+}
+
+// X always moves first, so it is X's turn whenever both have the same number of pieces
+pub fn turn(crosses: usize, circles: usize) -> Mark {
+    if crosses > circles {
+        Mark::O
+    } else {
+        Mark::X
+    }
+}
+
+// Rows from top to bottom separated by '/', with x, o and . for empty cells
+pub fn rows_notation<'a>(rows: impl Iterator<Item = &'a [Option<Mark>]>) -> String {
+    rows.map(|row| {
+        row.iter()
+            .map(|mark| match mark {
+                Some(Mark::X) => 'x',
+                Some(Mark::O) => 'o',
+                None => '.',
+            })
+            .collect::<String>()
+    })
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+// A plain copy of the 3x3 grid that can be built and inspected outside the library
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Board([[Option<Mark>; 3]; 3]);
+
+impl Board {
+    pub fn from_cells(cells: &Cells) -> Board {
+        let mut board = Board::default();
+        for (i, row) in cells.iter().enumerate().take(3) {
+            for (j, cell) in row.iter().enumerate().take(3) {
+                board.0[i][j] = match cell {
+                    GameCell::Empty => None,
+                    GameCell::Cross => Some(Mark::X),
+                    GameCell::Circle => Some(Mark::O),
+                };
+            }
+        }
+        board
+    }
+
+    pub fn of(app: &App) -> Option<Board> {
+        match game_state(app)? {
+            GameState::GameInProgress(cells, ..) | GameState::GameOver(_, cells) => {
+                Some(Board::from_cells(cells))
+            }
+        }
+    }
+
+    pub fn get(&self, (row, col): (usize, usize)) -> Option<Mark> {
+        self.0[row][col]
+    }
+
+    pub fn set(&mut self, (row, col): (usize, usize), mark: Option<Mark>) {
+        self.0[row][col] = mark;
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Option<Mark>; 3]> {
+        self.0.iter()
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), Option<Mark>)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, mark)| ((i, j), *mark)))
+    }
+
+    pub fn count(&self, mark: Mark) -> usize {
+        self.cells().filter(|(_, m)| *m == Some(mark)).count()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cells().all(|(_, mark)| mark.is_some())
+    }
+
+    pub fn winner(&self) -> Option<Mark> {
+        winner(3, |cell| self.get(cell))
+    }
+
+    // The empty cells that would complete a line for the mark
+    pub fn threats(&self, mark: Mark) -> Vec<(usize, usize)> {
+        threats(3, mark, |cell| self.get(cell))
+    }
+
+    pub fn turn(&self) -> Mark {
+        turn(self.count(Mark::X), self.count(Mark::O))
+    }
+
+    // Checks the position can be reached from an empty board with X moving first
+    // and that the game isn't already over, returning the side to move
+    pub fn validate(&self) -> Result<Mark, String> {
+        let (crosses, circles) = (self.count(Mark::X), self.count(Mark::O));
+        if crosses < circles || crosses > circles + 1 {
+            return Err(format!(
+                "X moves first, so X needs as many pieces as O or one more (X has {}, O has {})",
+                crosses, circles
+            ));
+        }
+        if let Some(mark) = self.winner() {
+            return Err(format!("{} already has three in a row", mark));
+        }
+        if self.is_full() {
+            return Err("The board is already full".to_string());
+        }
+        Ok(self.turn())
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", rows_notation(self.rows().map(|row| &row[..])))
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Board, String> {
+        let rows = s.split('/').collect::<Vec<_>>();
+        if rows.len() != 3 {
+            return Err(format!("Expected 3 rows separated by '/' in {:?}", s));
+        }
+        let mut board = Board::default();
+        for (i, row) in rows.iter().enumerate() {
+            if row.chars().count() != 3 {
+                return Err(format!("Expected 3 cells in row {:?}", row));
+            }
+            for (j, c) in row.chars().enumerate() {
+                board.0[i][j] = match c {
+                    'x' | 'X' => Some(Mark::X),
+                    'o' | 'O' => Some(Mark::O),
+                    '.' => None,
+                    _ => return Err(format!("Unknown cell {:?}, expected x, o or .", c)),
+                };
+            }
+        }
+        Ok(board)
+    }
+}
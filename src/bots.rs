@@ -0,0 +1,583 @@
+// Opponents built into the game, for when no external engine is given
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    board::{Board, Mark},
+    opponent::Opponent,
+    solver::{Solver, Value},
+};
+
+fn empty_cells(board: &Board) -> Vec<(usize, usize)> {
+    board
+        .cells()
+        .filter(|(_, mark)| mark.is_none())
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+// The cells where the mark would have two ways to win at once
+fn forks(board: &Board, mark: Mark) -> Vec<(usize, usize)> {
+    empty_cells(board)
+        .into_iter()
+        .filter(|&cell| {
+            let mut next = *board;
+            next.set(cell, Some(mark));
+            next.threats(mark).len() >= 2
+        })
+        .collect()
+}
+
+// Picks from the first non-empty group of moves, returning what to say about it
+fn first_choice(
+    rng: &mut StdRng,
+    choices: Vec<(Vec<(usize, usize)>, &'static str)>,
+) -> Result<((usize, usize), &'static str), String> {
+    choices
+        .into_iter()
+        .find_map(|(cells, remark)| Some((*cells.choose(rng)?, remark)))
+        .ok_or_else(|| "There are no empty cells left".to_string())
+}
+
+// Plays any empty cell
+pub struct Random {
+    rng: StdRng,
+}
+
+impl Random {
+    pub fn new() -> Random {
+        Random {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Default for Random {
+    fn default() -> Random {
+        Random::new()
+    }
+}
+
+impl Opponent for Random {
+    fn name(&self) -> String {
+        "Random".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        empty_cells(board)
+            .choose(&mut self.rng)
+            .copied()
+            .ok_or_else(|| "There are no empty cells left".to_string())
+    }
+}
+
+// Plays one of the best moves found by searching the whole game tree
+pub struct Perfect {
+    solver: Solver,
+    rng: StdRng,
+}
+
+impl Perfect {
+    pub fn new() -> Perfect {
+        Perfect {
+            solver: Solver::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Default for Perfect {
+    fn default() -> Perfect {
+        Perfect::new()
+    }
+}
+
+impl Opponent for Perfect {
+    fn name(&self) -> String {
+        "Perfect".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        self.solver
+            .best_moves(board)
+            .choose(&mut self.rng)
+            .copied()
+            .ok_or_else(|| "There are no moves left".to_string())
+    }
+}
+
+// How many of the last games the adaptive computer looks at
+const RECENT: usize = 5;
+// Moves the error rate by up to a tenth after every game
+const STEP: f64 = 0.2;
+
+struct Tuning {
+    error_rate: f64,
+    recent: VecDeque<f64>,
+}
+
+// The share of random moves an adaptive computer plays, shared between the computer and
+// whoever reports how its games went
+#[derive(Clone)]
+pub struct Difficulty(Arc<Mutex<Tuning>>);
+
+impl Difficulty {
+    pub fn new(error_rate: f64) -> Difficulty {
+        Difficulty(Arc::new(Mutex::new(Tuning {
+            error_rate: error_rate.clamp(0.0, 1.0),
+            recent: VecDeque::new(),
+        })))
+    }
+
+    fn tuning(&self) -> MutexGuard<'_, Tuning> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        self.tuning().error_rate
+    }
+
+    // From 0 for random moves only up to 10 for perfect play
+    pub fn level(&self) -> u8 {
+        ((1.0 - self.error_rate()) * 10.0).round() as u8
+    }
+
+    // Takes the opponent's score in a finished game, 1 for a win, 1/2 for a draw and 0 for
+    // a loss, and plays worse while the opponent scores under half in the recent games
+    pub fn record(&self, score: f64) {
+        let mut tuning = self.tuning();
+        if tuning.recent.len() == RECENT {
+            tuning.recent.pop_front();
+        }
+        tuning.recent.push_back(score);
+        let average = tuning.recent.iter().sum::<f64>() / tuning.recent.len() as f64;
+        tuning.error_rate = (tuning.error_rate + STEP * (0.5 - average)).clamp(0.0, 1.0);
+    }
+}
+
+// Plays a perfect move or, as often as the difficulty says, a random one
+pub struct Adaptive {
+    solver: Solver,
+    rng: StdRng,
+    difficulty: Difficulty,
+}
+
+impl Adaptive {
+    pub fn new(difficulty: Difficulty) -> Adaptive {
+        Adaptive {
+            solver: Solver::new(),
+            rng: StdRng::from_entropy(),
+            difficulty,
+        }
+    }
+}
+
+impl Opponent for Adaptive {
+    fn name(&self) -> String {
+        "Adaptive computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        let moves = if self.rng.gen_bool(self.difficulty.error_rate()) {
+            empty_cells(board)
+        } else {
+            self.solver.best_moves(board)
+        };
+        moves
+            .choose(&mut self.rng)
+            .copied()
+            .ok_or_else(|| "There are no moves left".to_string())
+    }
+}
+
+// Wins when it can and blocks when it has to, and otherwise goes for forks and two in a
+// rows
+pub struct Aggressive {
+    rng: StdRng,
+    remark: Option<&'static str>,
+}
+
+impl Aggressive {
+    pub fn new() -> Aggressive {
+        Aggressive {
+            rng: StdRng::from_entropy(),
+            remark: None,
+        }
+    }
+}
+
+impl Default for Aggressive {
+    fn default() -> Aggressive {
+        Aggressive::new()
+    }
+}
+
+impl Opponent for Aggressive {
+    fn name(&self) -> String {
+        "Aggressive computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        let me = board.turn();
+        let attacks = empty_cells(board)
+            .into_iter()
+            .filter(|&cell| {
+                let mut next = *board;
+                next.set(cell, Some(me));
+                !next.threats(me).is_empty()
+            })
+            .collect();
+        let (cell, remark) = first_choice(
+            &mut self.rng,
+            vec![
+                (board.threats(me), "Three in a row. Too easy."),
+                (board.threats(me.other()), "Fine, I'll block that. For now."),
+                (
+                    forks(board, me),
+                    "Two ways to win. Which one will you stop?",
+                ),
+                (attacks, "Here I come!"),
+                (empty_cells(board), "Just warming up."),
+            ],
+        )?;
+        self.remark = Some(remark);
+        Ok(cell)
+    }
+
+    fn remark(&self) -> Option<String> {
+        self.remark.map(str::to_string)
+    }
+}
+
+// Blocks lines and forks before anything else, and likes the centre and corners
+pub struct Defensive {
+    rng: StdRng,
+    remark: Option<&'static str>,
+}
+
+impl Defensive {
+    pub fn new() -> Defensive {
+        Defensive {
+            rng: StdRng::from_entropy(),
+            remark: None,
+        }
+    }
+}
+
+impl Default for Defensive {
+    fn default() -> Defensive {
+        Defensive::new()
+    }
+}
+
+impl Opponent for Defensive {
+    fn name(&self) -> String {
+        "Defensive computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        let me = board.turn();
+        let free = |cells: &[(usize, usize)]| {
+            cells
+                .iter()
+                .copied()
+                .filter(|&cell| board.get(cell).is_none())
+                .collect()
+        };
+        let (cell, remark) = first_choice(
+            &mut self.rng,
+            vec![
+                (board.threats(me), "Sorry, I had to."),
+                (board.threats(me.other()), "Not on my watch."),
+                (forks(board, me.other()), "I saw that fork coming."),
+                (free(&[(1, 1)]), "Safety first."),
+                (
+                    free(&[(0, 0), (0, 2), (2, 0), (2, 2)]),
+                    "Covering the corners.",
+                ),
+                (empty_cells(board), "Nothing to worry about."),
+            ],
+        )?;
+        self.remark = Some(remark);
+        Ok(cell)
+    }
+
+    fn remark(&self) -> Option<String> {
+        self.remark.map(str::to_string)
+    }
+}
+
+// Never plays a losing move, and among the rest picks the one that leaves the most
+// replies losing for the other side
+pub struct Trickster {
+    solver: Solver,
+    rng: StdRng,
+    remark: Option<&'static str>,
+}
+
+impl Trickster {
+    pub fn new() -> Trickster {
+        Trickster {
+            solver: Solver::new(),
+            rng: StdRng::from_entropy(),
+            remark: None,
+        }
+    }
+
+    // How many replies to the move lose for the other side
+    fn traps(&mut self, board: &Board, cell: (usize, usize)) -> usize {
+        let mut next = *board;
+        next.set(cell, Some(board.turn()));
+        self.solver
+            .moves(&next)
+            .into_iter()
+            .filter(|(_, value)| matches!(value, Value::Loss(_)))
+            .count()
+    }
+}
+
+impl Default for Trickster {
+    fn default() -> Trickster {
+        Trickster::new()
+    }
+}
+
+impl Opponent for Trickster {
+    fn name(&self) -> String {
+        "Trickster computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        let me = board.turn();
+        let mut traps = Vec::new();
+        for (cell, value) in self.solver.moves(board) {
+            if !matches!(value, Value::Loss(_)) {
+                traps.push((cell, self.traps(board, cell)));
+            }
+        }
+        let most = traps.iter().map(|(_, count)| *count).max().unwrap_or(0);
+        let trap = traps
+            .into_iter()
+            .filter(|(_, count)| most > 0 && *count == most)
+            .map(|(cell, _)| cell)
+            .collect();
+        let (cell, remark) = first_choice(
+            &mut self.rng,
+            vec![
+                (board.threats(me), "Gotcha!"),
+                (board.threats(me.other()), "Nice try."),
+                (trap, "Go on, that square looks safe."),
+                (self.solver.best_moves(board), "Hmm, nothing sneaky here."),
+            ],
+        )?;
+        self.remark = Some(remark);
+        Ok(cell)
+    }
+
+    fn remark(&self) -> Option<String> {
+        self.remark.map(str::to_string)
+    }
+}
+
+// Answers every move with the cell opposite it through the centre, unless it can win or
+// has to block
+pub struct Mirror {
+    rng: StdRng,
+    // the board after its last move, to tell which move was answered
+    last: Option<Board>,
+    remark: Option<&'static str>,
+}
+
+impl Mirror {
+    pub fn new() -> Mirror {
+        Mirror {
+            rng: StdRng::from_entropy(),
+            last: None,
+            remark: None,
+        }
+    }
+}
+
+impl Default for Mirror {
+    fn default() -> Mirror {
+        Mirror::new()
+    }
+}
+
+impl Opponent for Mirror {
+    fn name(&self) -> String {
+        "Mirror computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        let me = board.turn();
+        // a board missing any of the pieces after its last move is from a new game
+        let last = self
+            .last
+            .filter(|last| {
+                last.cells()
+                    .all(|(cell, mark)| mark.is_none() || board.get(cell) == mark)
+            })
+            .unwrap_or_default();
+        let theirs = |new_only: bool| {
+            board
+                .cells()
+                .filter(|&(cell, mark)| {
+                    mark == Some(me.other()) && !(new_only && last.get(cell) == mark)
+                })
+                .map(|(cell, _)| (2 - cell.0, 2 - cell.1))
+                .filter(|&cell| board.get(cell).is_none())
+                .collect()
+        };
+        let (cell, remark) = first_choice(
+            &mut self.rng,
+            vec![
+                (board.threats(me), "Oh look, I won."),
+                (board.threats(me.other()), "Can't copy that one."),
+                (theirs(true), "Monkey see, monkey do."),
+                (theirs(false), "Anything you can do..."),
+                (empty_cells(board), "Nothing left to copy."),
+            ],
+        )?;
+        let mut after = *board;
+        after.set(cell, Some(me));
+        self.last = Some(after);
+        self.remark = Some(remark);
+        Ok(cell)
+    }
+
+    fn remark(&self) -> Option<String> {
+        self.remark.map(str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{cell_name, parse_cell, parse_position};
+
+    // The cells the opponent picks from the position over a few tries, as it picks at
+    // random between moves it likes as much
+    fn choices(opponent: &mut dyn Opponent, position: &str) -> Vec<String> {
+        let board = parse_position(position).unwrap();
+        let mut cells: Vec<String> = (0..20)
+            .map(|_| cell_name(opponent.choose(&board, Duration::ZERO).unwrap()))
+            .collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+
+    fn bots() -> Vec<Box<dyn Opponent>> {
+        vec![
+            Box::new(Perfect::new()),
+            Box::new(Adaptive::new(Difficulty::new(0.0))),
+            Box::new(Aggressive::new()),
+            Box::new(Defensive::new()),
+            Box::new(Trickster::new()),
+            Box::new(Mirror::new()),
+        ]
+    }
+
+    #[test]
+    fn every_bot_wins_before_blocking() {
+        for mut bot in bots() {
+            // X wins on c1, and O would win on c2
+            assert_eq!(
+                choices(bot.as_mut(), "xx./oo./..."),
+                ["c1"],
+                "{}",
+                bot.name()
+            );
+        }
+    }
+
+    #[test]
+    fn every_bot_blocks_before_forking() {
+        for mut bot in bots() {
+            // O wins on c1 unless X blocks it, though b2 would give X two ways to win
+            assert_eq!(
+                choices(bot.as_mut(), ".../..o/xxo"),
+                ["c1"],
+                "{}",
+                bot.name()
+            );
+        }
+    }
+
+    #[test]
+    fn aggressive_forks_when_nothing_needs_blocking() {
+        assert_eq!(choices(&mut Aggressive::new(), ".../..o/oxx"), ["b2"]);
+    }
+
+    #[test]
+    fn defensive_blocks_forks() {
+        // X would have two ways to win after b2
+        assert_eq!(choices(&mut Defensive::new(), ".../.../oxx"), ["b2"]);
+    }
+
+    #[test]
+    fn trickster_never_plays_a_losing_move() {
+        let mut solver = Solver::new();
+        let board = parse_position(".../.../oxx").unwrap();
+        for cell in choices(&mut Trickster::new(), ".../.../oxx") {
+            let mut next = board;
+            next.set(parse_cell(&cell).unwrap(), Some(Mark::O));
+            assert!(!matches!(solver.value(&next), Value::Win(_)), "{}", cell);
+        }
+    }
+
+    #[test]
+    fn mirror_copies_when_nothing_needs_blocking() {
+        assert_eq!(choices(&mut Mirror::new(), "x../.../..."), ["c3"]);
+    }
+
+    #[test]
+    fn difficulty_follows_the_results() {
+        let difficulty = Difficulty::new(0.3);
+        assert_eq!(difficulty.level(), 7);
+        // the opponent winning makes the computer play better
+        difficulty.record(1.0);
+        assert_eq!(difficulty.level(), 8);
+        difficulty.record(1.0);
+        assert_eq!(difficulty.level(), 9);
+        for _ in 0..10 {
+            difficulty.record(1.0);
+        }
+        assert_eq!(difficulty.level(), 10);
+        assert_eq!(difficulty.error_rate(), 0.0);
+
+        // and losing makes it play worse, once the wins drop out of the recent games
+        let mut levels = Vec::new();
+        for _ in 0..20 {
+            difficulty.record(0.0);
+            levels.push(difficulty.level());
+        }
+        assert!(levels.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert_eq!(levels.last(), Some(&0));
+        assert_eq!(difficulty.error_rate(), 1.0);
+
+        // even results leave it where it is
+        for _ in 0..5 {
+            difficulty.record(0.5);
+        }
+        let level = difficulty.level();
+        difficulty.record(0.5);
+        assert_eq!(difficulty.level(), level);
+    }
+
+    #[test]
+    fn difficulty_is_shared() {
+        let difficulty = Difficulty::new(2.0);
+        assert_eq!(difficulty.level(), 0);
+        let adaptive = difficulty.clone();
+        difficulty.record(1.0);
+        assert_eq!(adaptive.error_rate(), difficulty.error_rate());
+        assert_eq!(Difficulty::new(-1.0).level(), 10);
+    }
+}
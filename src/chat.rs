@@ -0,0 +1,68 @@
+use tictactoe_library::update::Key;
+
+// Sent with the number keys during a network game
+pub const QUICK: [&str; 4] = ["gg", "nice move", "good luck", "well played"];
+
+// messages kept for scrolling back
+const SCROLLBACK: usize = 200;
+// longest message that can be typed, in characters
+const MAX_LENGTH: usize = 200;
+
+// Messages between the players of a network game
+pub struct Chat {
+    // who said what, with no name for notices from the game itself
+    pub messages: Vec<(Option<String>, String)>,
+    // the message being typed, while in text input mode
+    pub input: Option<String>,
+    // how many messages back from the latest the pane is scrolled
+    pub scroll: usize,
+}
+
+impl Chat {
+    pub fn new() -> Chat {
+        Chat {
+            messages: Vec::new(),
+            input: None,
+            scroll: 0,
+        }
+    }
+
+    pub fn push(&mut self, from: Option<String>, text: String) {
+        if self.messages.len() == SCROLLBACK {
+            self.messages.remove(0);
+        }
+        self.messages.push((from, text));
+        self.scroll = 0;
+    }
+
+    pub fn scroll_back(&mut self) {
+        self.scroll = (self.scroll + 1).min(self.messages.len().saturating_sub(1));
+    }
+
+    pub fn scroll_forward(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    // Handles a key in text input mode, returning a message once it is sent
+    pub fn update(&mut self, key: &Key) -> Option<String> {
+        let input = self.input.as_mut()?;
+        match key {
+            Key::Esc | Key::Char('\t') => self.input = None,
+            Key::Enter => {
+                let text = self.input.take().unwrap_or_default();
+                let text = text.trim();
+                if !text.is_empty() {
+                    return Some(text.to_string());
+                }
+            }
+            Key::Char('\u{8}') => {
+                input.pop();
+            }
+            Key::Up => self.scroll_back(),
+            Key::Down => self.scroll_forward(),
+            Key::Char(c) if !c.is_control() && input.chars().count() < MAX_LENGTH => input.push(*c),
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,106 @@
+use std::{env, fs, time::Duration};
+
+use tictactoe_tui::{
+    board::Board,
+    learning,
+    notation::{self, Game},
+    protocol,
+};
+
+pub struct Config {
+    // ask before quitting, resetting or replacing a game that has moves on the board
+    pub confirm: bool,
+    // position to start the first game from
+    pub position: Option<Board>,
+    // game to replay before handing over to the players
+    pub game: Option<Game>,
+    // command line of an external engine playing as player 2
+    pub engine: Option<String>,
+    // how long the engine or the Monte Carlo computer may think about each move
+    pub engine_time: Duration,
+    // games the Monte Carlo computer plays out before each move
+    pub playouts: usize,
+    // share of random moves the adaptive computer starts the session with
+    pub error_rate: f64,
+    // table the learned computer plays from
+    pub table: String,
+    // address of a lobby server to play other clients on
+    pub connect: Option<String>,
+    // the name other clients see
+    pub name: String,
+}
+
+impl Config {
+    pub fn from_args() -> Result<Config, String> {
+        let mut config = Config {
+            confirm: true,
+            position: None,
+            game: None,
+            engine: None,
+            engine_time: Duration::from_secs(2),
+            playouts: 20000,
+            error_rate: 0.3,
+            table: learning::default_path(3),
+            connect: None,
+            name: env::var("USER")
+                .or_else(|_| env::var("USERNAME"))
+                .unwrap_or_else(|_| "Player".to_string()),
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-confirm" => config.confirm = false,
+                "--position" => {
+                    config.position = Some(notation::parse_position(&value(&mut args, &arg)?)?)
+                }
+                "--load" => {
+                    let path = value(&mut args, &arg)?;
+                    let text = fs::read_to_string(&path)
+                        .map_err(|err| format!("could not read {}: {}", path, err))?;
+                    config.game = Some(text.parse()?);
+                }
+                "--engine" => config.engine = Some(value(&mut args, &arg)?),
+                "--engine-time" => {
+                    let millis = value(&mut args, &arg)?
+                        .parse()
+                        .map_err(|_| format!("{} needs a number of milliseconds", arg))?;
+                    config.engine_time = Duration::from_millis(millis);
+                }
+                "--playouts" => {
+                    config.playouts = value(&mut args, &arg)?
+                        .parse()
+                        .ok()
+                        .filter(|playouts| *playouts > 0)
+                        .ok_or_else(|| format!("{} needs a number of games above 0", arg))?;
+                }
+                "--table" => config.table = value(&mut args, &arg)?,
+                "--connect" => config.connect = Some(value(&mut args, &arg)?),
+                "--name" => config.name = protocol::clean_name(&value(&mut args, &arg)?),
+                "--error-rate" => {
+                    let percent: u8 = value(&mut args, &arg)?
+                        .parse()
+                        .ok()
+                        .filter(|percent| *percent <= 100)
+                        .ok_or_else(|| format!("{} needs a percentage from 0 to 100", arg))?;
+                    config.error_rate = percent as f64 / 100.0;
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        if config.position.is_some() && config.game.is_some() {
+            return Err("--position and --load can't be used together".to_string());
+        }
+        let offline = config.engine.is_some() || config.position.is_some() || config.game.is_some();
+        if config.connect.is_some() && offline {
+            return Err("--connect can't be used with --engine, --position or --load".to_string());
+        }
+        if config.name.is_empty() {
+            return Err("--name can't be empty".to_string());
+        }
+        Ok(config)
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{} needs a value", flag))
+}
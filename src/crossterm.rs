@@ -14,9 +14,13 @@ use tui::{
     Terminal,
 };
 
-use crate::ui;
+use crate::{config::Config, session::Session, ui};
 
 pub fn run() -> Result<(), Box<dyn Error>> {
+    // create app, setting up the position or game it starts from
+    let config = Config::from_args()?;
+    let session = Session::new(App::new(), config)?;
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -24,9 +28,8 @@ pub fn run() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let app = App::new();
-    let res = run_app(&mut terminal, app);
+    // run app
+    let res = run_app(&mut terminal, session);
 
     // restore terminal
     disable_raw_mode()?;
@@ -44,11 +47,11 @@ pub fn run() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut session: Session) -> io::Result<()> {
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_millis(250);
     loop {
-        terminal.draw(|f| ui::draw(f, &mut app))?;
+        terminal.draw(|f| ui::draw(f, &mut session))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
@@ -63,15 +66,19 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                     event::KeyCode::Up => Key::Up,
                     event::KeyCode::Left => Key::Left,
                     event::KeyCode::Right => Key::Right,
+                    // the library has no keys for these, so they pass as control characters
+                    event::KeyCode::Tab => Key::Char('\t'),
+                    event::KeyCode::Backspace => Key::Char('\u{8}'),
                     _ => Key::Unknown,
                 };
-                app.update(key);
+                session.update(key);
             }
         }
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
         }
-        if let AppState::Quit = app.state {
+        session.tick();
+        if let AppState::Quit = session.app.state {
             return Ok(());
         }
     }
@@ -0,0 +1,46 @@
+use tictactoe_library::update::Key;
+use tictactoe_tui::board::{Board, Mark};
+
+pub enum Edit {
+    Continue,
+    Cancel,
+    Start(Board),
+}
+
+// Lets the player set up any legal position before starting a game from it
+pub struct Editor {
+    pub board: Board,
+    pub cursor: (usize, usize),
+    pub error: Option<String>,
+}
+
+impl Editor {
+    pub fn new() -> Editor {
+        Editor {
+            board: Board::default(),
+            cursor: (0, 0),
+            error: None,
+        }
+    }
+
+    pub fn update(&mut self, key: Key) -> Edit {
+        let (row, col) = self.cursor;
+        self.error = None;
+        match key {
+            Key::Up => self.cursor = (row.saturating_sub(1), col),
+            Key::Down => self.cursor = ((row + 1).min(2), col),
+            Key::Left => self.cursor = (row, col.saturating_sub(1)),
+            Key::Right => self.cursor = (row, (col + 1).min(2)),
+            Key::Char('x' | 'X') => self.board.set(self.cursor, Some(Mark::X)),
+            Key::Char('o' | 'O') => self.board.set(self.cursor, Some(Mark::O)),
+            Key::Char(' ' | '.') => self.board.set(self.cursor, None),
+            Key::Esc => return Edit::Cancel,
+            Key::Enter => match self.board.validate() {
+                Ok(_) => return Edit::Start(self.board),
+                Err(error) => self.error = Some(error),
+            },
+            _ => {}
+        }
+        Edit::Continue
+    }
+}
@@ -0,0 +1,216 @@
+// External engines speaking a small line based protocol over stdin and stdout,
+// see the Engines section of the README
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::Board,
+    notation::{self, parse_cell},
+    opponent::Opponent,
+};
+
+// Splits a command line into the program and its arguments at spaces, keeping anything
+// in single or double quotes together so paths with spaces can be passed. Outside single
+// quotes a backslash escapes a quote, a space or another backslash, and any other
+// backslash, like those in Windows paths or one at the end, is kept as it is.
+pub fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => {
+                let escaped = chars
+                    .next_if(|&next| matches!(next, '"' | '\'' | '\\') || next.is_whitespace());
+                word.get_or_insert_with(String::new)
+                    .push(escaped.unwrap_or('\\'));
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(format!(
+            "Unclosed quote in the engine command {:?}",
+            command
+        ));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// extra time for the engine's answer to make it back after its budget runs out
+const GRACE: Duration = Duration::from_millis(500);
+
+pub struct Engine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    pub fn spawn(command: &str) -> Result<Engine, String> {
+        let words = split_command(command)?;
+        let (program, args) = words.split_first().ok_or("The engine command is empty")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("Could not start engine {:?}: {}", command, err))?;
+        let stdin = child.stdin.take().ok_or("Could not write to the engine")?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("Could not read from the engine")?;
+
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let name = Path::new(program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| program.to_string());
+        Ok(Engine {
+            name,
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| format!("{} has exited", self.name))
+    }
+}
+
+impl Opponent for Engine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose(&mut self, board: &Board, budget: Duration) -> Result<(usize, usize), String> {
+        // drop anything left over from a move that was given up on
+        while self.lines.try_recv().is_ok() {}
+        self.send(&format!(
+            "position {}",
+            notation::position(board, board.turn())
+        ))?;
+        self.send(&format!("go {}", budget.as_millis()))?;
+
+        let deadline = Instant::now() + budget + GRACE;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!(
+                        "{} did not answer within {} ms",
+                        self.name,
+                        budget.as_millis()
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("{} has exited", self.name))
+                }
+            };
+            // anything other than the answer is the engine talking to itself
+            if let Some(cell) = line.trim().strip_prefix("bestmove ") {
+                return parse_cell(cell.trim())
+                    .map_err(|err| format!("{} sent a bad move: {}", self.name, err));
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + GRACE;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(command: &str) -> Vec<String> {
+        split_command(command).unwrap()
+    }
+
+    #[test]
+    fn splits_at_spaces() {
+        assert_eq!(split("engine --depth  3 "), ["engine", "--depth", "3"]);
+        assert!(split("   ").is_empty());
+    }
+
+    #[test]
+    fn keeps_quoted_words_together() {
+        assert_eq!(
+            split("'my engines/engine' --name \"Deep Blue\""),
+            ["my engines/engine", "--name", "Deep Blue"]
+        );
+        assert_eq!(split("--name=\"a b\"c"), ["--name=a bc"]);
+        assert_eq!(split("engine ''"), ["engine", ""]);
+        assert_eq!(split("\"it's\" 'say \"hi\"'"), ["it's", "say \"hi\""]);
+    }
+
+    #[test]
+    fn backslashes_escape_quotes_and_spaces() {
+        assert_eq!(
+            split(r#"my\ engine \"quoted\""#),
+            ["my engine", "\"quoted\""]
+        );
+        assert_eq!(split(r#""a \" b" c\\d"#), ["a \" b", r"c\d"]);
+        // other backslashes are kept, so Windows paths work unquoted
+        assert_eq!(split(r"C:\engines\engine.exe"), [r"C:\engines\engine.exe"]);
+        assert_eq!(split(r"'C:\my engines\'"), [r"C:\my engines\"]);
+    }
+
+    #[test]
+    fn keeps_trailing_backslashes() {
+        assert_eq!(split(r"engine dir\"), ["engine", r"dir\"]);
+        assert_eq!(split(r"engine \"), ["engine", r"\"]);
+    }
+
+    #[test]
+    fn rejects_unclosed_quotes() {
+        assert!(split_command("'my engines/engine --depth 3").is_err());
+        assert!(split_command("engine \"").is_err());
+        // an escaped quote doesn't close one
+        assert!(split_command(r#"engine "dir\""#).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+// Rebuilds the moves of the current game by comparing the board after every update,
+// since the library only keeps the current position
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tictactoe_library::{
+    app::App,
+    game::{GameState, Player},
+};
+
+use tictactoe_tui::{
+    board::{game_state, Board, Mark},
+    notation::{Game, Outcome},
+};
+
+// Start menu entries in order, as (mode, name of player 2)
+pub const MODES: [(&str, &str); 3] = [
+    ("human", "Human"),
+    ("random", "Random computer"),
+    ("smart", "Smart computer"),
+];
+
+pub struct History {
+    mode: String,
+    // the names of the players of X and O
+    players: [String; 2],
+    start: Board,
+    moves: Vec<(usize, usize)>,
+    last: Option<Board>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            mode: MODES[0].0.to_string(),
+            players: ["Human".to_string(), MODES[0].1.to_string()],
+            start: Board::default(),
+            moves: Vec::new(),
+            last: None,
+        }
+    }
+
+    // Called with the start menu row the next game is started from
+    pub fn set_mode(&mut self, row: usize) {
+        let (mode, opponent) = MODES[row.min(MODES.len() - 1)];
+        self.set_opponent(mode, opponent);
+    }
+
+    // Against a computer or on the same keyboard, where the human plays X
+    pub fn set_opponent(&mut self, mode: &str, opponent: &str) {
+        self.set_players(mode, ["Human", opponent]);
+    }
+
+    pub fn set_players(&mut self, mode: &str, players: [&str; 2]) {
+        self.mode = mode.to_string();
+        self.players = players.map(str::to_string);
+    }
+
+    pub fn observe(&mut self, app: &App) {
+        let board = match Board::of(app) {
+            Some(board) => board,
+            None => return,
+        };
+        let last = match self.last.replace(board) {
+            Some(last) if last != board => last,
+            Some(_) => return,
+            None => return self.restart(board),
+        };
+        let mut added = Vec::new();
+        for (cell, mark) in board.cells() {
+            match (last.get(cell), mark) {
+                (None, Some(mark)) => added.push((cell, mark)),
+                (Some(before), Some(after)) if before == after => {}
+                (None, None) => {}
+                // a piece went missing, so this is a new game
+                _ => return self.restart(board),
+            }
+        }
+        // the computer answers within the same update, so the side to move comes first
+        let to_move = last.turn();
+        added.sort_by_key(|(_, mark)| *mark != to_move);
+        match added.as_slice() {
+            [(cell, _)] => self.moves.push(*cell),
+            [(first, a), (second, b)] if a != b => self.moves.extend([*first, *second]),
+            _ => self.restart(board),
+        }
+    }
+
+    fn restart(&mut self, board: Board) {
+        self.start = board;
+        self.moves.clear();
+    }
+
+    pub fn game(&self, app: &App) -> Game {
+        let outcome = match game_state(app) {
+            Some(GameState::GameOver(winner, _)) => match winner {
+                Some(Player::Player1) => Outcome::Win(Mark::X),
+                Some(Player::Player2) => Outcome::Win(Mark::O),
+                None => Outcome::Draw,
+            },
+            _ => Outcome::Unfinished,
+        };
+        Game {
+            headers: vec![
+                ("Player1".to_string(), self.players[0].clone()),
+                ("Player2".to_string(), self.players[1].clone()),
+                ("Mode".to_string(), self.mode.clone()),
+                ("Date".to_string(), today()),
+            ],
+            start: self.start,
+            moves: self.moves.clone(),
+            outcome,
+        }
+    }
+}
+
+// The current UTC date as YYYY.MM.DD
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    // days since 1970-01-01 to a civil date, from Howard Hinnant's date algorithms
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,301 @@
+// Learns how good every position is by playing against itself, keeping a table with a
+// value for each position reached. Works on any square board with lines as long as its
+// side, so 4x4 tables can be trained even though the game itself is 3x3.
+use std::{collections::HashMap, fs, time::Duration};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    board::{self, Board, Mark},
+    opponent::Opponent,
+};
+
+// Where tables are saved when no other file is given
+pub fn default_path(size: usize) -> String {
+    format!("learned-{0}x{0}.txt", size)
+}
+
+// Board is fixed to the 3x3 game the library plays, so positions on bigger boards need
+// their own grid, which uses the rules from board.rs for any size
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid {
+    size: usize,
+    cells: Vec<Option<Mark>>,
+}
+
+impl Grid {
+    pub fn new(size: usize) -> Grid {
+        Grid {
+            size,
+            cells: vec![None; size * size],
+        }
+    }
+
+    pub fn from_board(board: &Board) -> Grid {
+        Grid {
+            size: 3,
+            cells: board.cells().map(|(_, mark)| mark).collect(),
+        }
+    }
+
+    // The same position as the game's board, if the grid is 3x3
+    pub fn to_board(&self) -> Option<Board> {
+        if self.size != 3 {
+            return None;
+        }
+        let mut board = Board::default();
+        for (cell, &mark) in self.cells.iter().enumerate() {
+            board.set((cell / 3, cell % 3), mark);
+        }
+        Some(board)
+    }
+
+    fn get(&self, (row, col): (usize, usize)) -> Option<Mark> {
+        self.cells[row * self.size + col]
+    }
+
+    // Cells are numbered row by row from the top left
+    pub fn empty_cells(&self) -> Vec<usize> {
+        (0..self.cells.len())
+            .filter(|&cell| self.cells[cell].is_none())
+            .collect()
+    }
+
+    pub fn turn(&self) -> Mark {
+        let count = |mark| self.cells.iter().filter(|&&m| m == Some(mark)).count();
+        board::turn(count(Mark::X), count(Mark::O))
+    }
+
+    pub fn play(&self, cell: usize) -> Grid {
+        let mut next = self.clone();
+        next.cells[cell] = Some(self.turn());
+        next
+    }
+
+    pub fn winner(&self) -> Option<Mark> {
+        board::winner(self.size, |cell| self.get(cell))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || self.cells.iter().all(Option::is_some)
+    }
+
+    // Written like the board in the position notation
+    fn key(&self) -> String {
+        board::rows_notation(self.cells.chunks(self.size))
+    }
+}
+
+// Any empty cell
+pub fn random_move(grid: &Grid, rng: &mut StdRng) -> Option<usize> {
+    grid.empty_cells().choose(rng).copied()
+}
+
+// The value of positions for the side that just moved into them, from -1 for a sure
+// loss to 1 for a sure win
+pub struct Table {
+    pub size: usize,
+    values: HashMap<String, f64>,
+}
+
+impl Table {
+    pub fn new(size: usize) -> Table {
+        Table {
+            size,
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn value(&self, grid: &Grid) -> f64 {
+        match grid.winner() {
+            Some(_) => 1.0,
+            None => self.values.get(&grid.key()).copied().unwrap_or(0.0),
+        }
+    }
+
+    // The move leading to the best position for the side to move, picking at random
+    // between equally good ones
+    pub fn best_move(&self, grid: &Grid, rng: &mut StdRng) -> Option<usize> {
+        let moves: Vec<(usize, f64)> = grid
+            .empty_cells()
+            .into_iter()
+            .map(|cell| (cell, self.value(&grid.play(cell))))
+            .collect();
+        let best = moves
+            .iter()
+            .map(|(_, value)| *value)
+            .fold(f64::MIN, f64::max);
+        let best: Vec<usize> = moves
+            .into_iter()
+            .filter(|(_, value)| *value == best)
+            .map(|(cell, _)| cell)
+            .collect();
+        best.choose(rng).copied()
+    }
+
+    // Plays a game against itself, exploring a random move as often as epsilon says,
+    // then moves the value of every position each side reached towards the one after it
+    // and the last one towards the result
+    pub fn self_play(&mut self, rng: &mut StdRng, alpha: f64, epsilon: f64) {
+        let mut grid = Grid::new(self.size);
+        let mut reached: [Vec<Grid>; 2] = [Vec::new(), Vec::new()];
+        while !grid.is_over() {
+            let side = (grid.turn() == Mark::O) as usize;
+            let cell = if rng.gen_bool(epsilon) {
+                random_move(&grid, rng)
+            } else {
+                self.best_move(&grid, rng)
+            };
+            grid = match cell {
+                Some(cell) => grid.play(cell),
+                None => break,
+            };
+            reached[side].push(grid.clone());
+        }
+        for (side, positions) in reached.iter().enumerate() {
+            let mark = if side == 0 { Mark::X } else { Mark::O };
+            let mut target = match grid.winner() {
+                Some(winner) if winner == mark => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            };
+            for position in positions.iter().rev() {
+                let value = self.values.entry(position.key()).or_insert(0.0);
+                *value += alpha * (target - *value);
+                target = *value;
+            }
+        }
+    }
+
+    // One position and its value per line after a line with the size
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = format!("size {}\n", self.size);
+        let mut values: Vec<_> = self.values.iter().collect();
+        values.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in values {
+            text.push_str(&format!("{} {}\n", key, value));
+        }
+        fs::write(path, text)
+            .map_err(|err| format!("Could not save the table to {}: {}", path, err))
+    }
+
+    pub fn load(path: &str) -> Result<Table, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read the table {}: {}", path, err))?;
+        let mut lines = text.lines();
+        let size = lines
+            .next()
+            .and_then(|line| line.strip_prefix("size "))
+            .and_then(|size| size.trim().parse().ok())
+            .ok_or_else(|| format!("{} doesn't start with the board size", path))?;
+        let mut table = Table::new(size);
+        for line in lines {
+            let value = line
+                .split_once(' ')
+                .and_then(|(key, value)| Some((key.to_string(), value.trim().parse().ok()?)));
+            match value {
+                Some((key, value)) => table.values.insert(key, value),
+                None => return Err(format!("Malformed line in {}: {:?}", path, line)),
+            };
+        }
+        Ok(table)
+    }
+}
+
+// Plays the best move according to a table learned with the train subcommand
+pub struct Learned {
+    table: Table,
+    rng: StdRng,
+}
+
+impl Learned {
+    pub fn load(path: &str) -> Result<Learned, String> {
+        let table = Table::load(path)?;
+        if table.size != 3 {
+            return Err(format!(
+                "{} was learned on {}x{} boards, but the game is played on 3x3",
+                path, table.size, table.size
+            ));
+        }
+        Ok(Learned {
+            table,
+            rng: StdRng::from_entropy(),
+        })
+    }
+}
+
+impl Opponent for Learned {
+    fn name(&self) -> String {
+        "Learned computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, _budget: Duration) -> Result<(usize, usize), String> {
+        self.table
+            .best_move(&Grid::from_board(board), &mut self.rng)
+            .map(|cell| (cell / 3, cell % 3))
+            .ok_or_else(|| "There are no empty cells left".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn temp_file(name: &str) -> String {
+        let path = env::temp_dir().join(format!("{}-{}.txt", name, process::id()));
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn tables_round_trip() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for size in [3, 4] {
+            let mut table = Table::new(size);
+            for _ in 0..200 {
+                table.self_play(&mut rng, 0.2, 0.1);
+            }
+            assert!(!table.is_empty());
+            let path = temp_file(&format!("table-{}", size));
+            table.save(&path).unwrap();
+            let loaded = Table::load(&path);
+            fs::remove_file(&path).unwrap();
+            let loaded = loaded.unwrap();
+            assert_eq!(loaded.size, size);
+            assert_eq!(loaded.values, table.values);
+        }
+    }
+
+    #[test]
+    fn malformed_tables_are_rejected() {
+        let path = temp_file("malformed");
+        for text in [
+            "",
+            "x.o/.x./... 0.5\n",
+            "size 3\nx.o/.x./...\n",
+            "size 3\nx.o/.x./... high\n",
+        ] {
+            fs::write(&path, text).unwrap();
+            assert!(Table::load(&path).is_err(), "{:?}", text);
+        }
+        fs::remove_file(&path).unwrap();
+        assert!(Table::load(&path).is_err());
+    }
+
+    #[test]
+    fn learned_needs_a_3x3_table() {
+        let path = temp_file("learned-4x4");
+        Table::new(4).save(&path).unwrap();
+        let learned = Learned::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(learned.is_err());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod autopilot;
+pub mod board;
+pub mod bots;
+pub mod engine;
+pub mod learning;
+pub mod mcts;
+pub mod notation;
+pub mod opponent;
+pub mod protocol;
+pub mod solver;
@@ -0,0 +1,84 @@
+use tictactoe_library::update::Key;
+use tictactoe_tui::protocol::Reply;
+
+pub enum LobbyAction {
+    Continue,
+    Refresh,
+    Create,
+    Join(u32),
+    Watch(u32),
+    Leave,
+    Quit,
+}
+
+pub struct RoomEntry {
+    pub id: u32,
+    pub open: bool,
+    pub players: String,
+}
+
+// Lists the rooms on the lobby server to create or join one
+pub struct Lobby {
+    pub rooms: Vec<RoomEntry>,
+    pub selected: usize,
+    // the room this client is in, waiting for an opponent
+    pub waiting: Option<u32>,
+    pub message: Option<String>,
+    // rooms arriving from the server until the end of the list
+    listing: Vec<RoomEntry>,
+}
+
+impl Lobby {
+    pub fn new(waiting: Option<u32>) -> Lobby {
+        Lobby {
+            rooms: Vec::new(),
+            selected: 0,
+            waiting,
+            message: None,
+            listing: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, key: Key) -> LobbyAction {
+        self.message = None;
+        match key {
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => {
+                self.selected = (self.selected + 1).min(self.rooms.len().saturating_sub(1))
+            }
+            Key::Char('r' | 'R') => return LobbyAction::Refresh,
+            Key::Char('q' | 'Q') => return LobbyAction::Quit,
+            Key::Esc if self.waiting.is_some() => return LobbyAction::Leave,
+            _ if self.waiting.is_some() => {
+                self.message = Some("Press Esc to leave your room first".to_string())
+            }
+            Key::Char('c' | 'C') => return LobbyAction::Create,
+            Key::Enter => match self.rooms.get(self.selected) {
+                Some(room) if room.open => return LobbyAction::Join(room.id),
+                Some(room) => self.message = Some(format!("Room {} is full", room.id)),
+                None => {
+                    self.message = Some("There are no rooms yet, press C to create one".to_string())
+                }
+            },
+            Key::Char('w' | 'W') => match self.rooms.get(self.selected) {
+                Some(room) => return LobbyAction::Watch(room.id),
+                None => self.message = Some("There are no rooms to watch yet".to_string()),
+            },
+            _ => {}
+        }
+        LobbyAction::Continue
+    }
+
+    pub fn receive(&mut self, reply: Reply) {
+        match reply {
+            Reply::Room { id, open, players } => self.listing.push(RoomEntry { id, open, players }),
+            Reply::End => {
+                self.rooms = std::mem::take(&mut self.listing);
+                self.selected = self.selected.min(self.rooms.len().saturating_sub(1));
+            }
+            Reply::Created(id) => self.waiting = Some(id),
+            Reply::Error(message) => self.message = Some(message),
+            _ => {}
+        }
+    }
+}
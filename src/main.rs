@@ -1,10 +1,25 @@
+mod chat;
+mod config;
 mod crossterm;
+mod editor;
+mod history;
+mod lobby;
+mod session;
+mod train;
 mod ui;
 
 use crate::crossterm::run;
-use std::error::Error;
+use std::{env, error::Error, process};
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    if args.next().as_deref() == Some("train") {
+        if let Err(message) = train::run(args) {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+        return Ok(());
+    }
     run()?;
     Ok(())
 }
@@ -0,0 +1,312 @@
+// Monte Carlo tree search, which plays random games from the position and steers them
+// with UCT towards the moves that have done best so far. It needs no knowledge of the
+// game beyond its rules, so it works on Grid boards of any size, where the solver's
+// exhaustive search is out of reach.
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    board::{Board, Mark},
+    learning::{self, Grid},
+    opponent::Opponent,
+};
+
+// How much UCT favours moves that have been tried less, the usual square root of two
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+// How often a move from the searched position was played out and how it did
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Visits {
+    pub cell: usize,
+    pub visits: u32,
+    // wins for the side to move, with draws counting half
+    pub score: f64,
+}
+
+impl Visits {
+    pub fn win_rate(&self) -> f64 {
+        self.score / self.visits.max(1) as f64
+    }
+}
+
+struct Node {
+    // the move leading to the node and the side that played it
+    cell: usize,
+    mover: Mark,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<usize>,
+    visits: u32,
+    score: f64,
+}
+
+struct Tree {
+    root: Grid,
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(root: &Grid, rng: &mut StdRng) -> Tree {
+        let mut tree = Tree {
+            root: root.clone(),
+            nodes: Vec::new(),
+        };
+        tree.add(root, 0, root.turn().other(), None, rng);
+        tree
+    }
+
+    fn add(
+        &mut self,
+        grid: &Grid,
+        cell: usize,
+        mover: Mark,
+        parent: Option<usize>,
+        rng: &mut StdRng,
+    ) -> usize {
+        let mut untried = if grid.is_over() {
+            Vec::new()
+        } else {
+            grid.empty_cells()
+        };
+        untried.shuffle(rng);
+        self.nodes.push(Node {
+            cell,
+            mover,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            score: 0.0,
+        });
+        let node = self.nodes.len() - 1;
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(node);
+        }
+        node
+    }
+
+    // Follows UCT down to a move not tried yet, plays the rest of the game at random and
+    // counts the result for every move on the way
+    fn playout(&mut self, rng: &mut StdRng) {
+        let mut grid = self.root.clone();
+        let mut node = 0;
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select(node);
+            grid = grid.play(self.nodes[node].cell);
+        }
+        if let Some(cell) = self.nodes[node].untried.pop() {
+            let mover = grid.turn();
+            grid = grid.play(cell);
+            node = self.add(&grid, cell, mover, Some(node), rng);
+        }
+        while !grid.is_over() {
+            match learning::random_move(&grid, rng) {
+                Some(cell) => grid = grid.play(cell),
+                None => break,
+            }
+        }
+        let winner = grid.winner();
+        let mut next = Some(node);
+        while let Some(node) = next {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.score += match winner {
+                Some(mark) if mark == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            next = node.parent;
+        }
+    }
+
+    // The child with the best upper confidence bound
+    fn select(&self, node: usize) -> usize {
+        let log_visits = (self.nodes[node].visits as f64).ln();
+        let bound = |child: usize| {
+            let child = &self.nodes[child];
+            let visits = child.visits as f64;
+            child.score / visits + EXPLORATION * (log_visits / visits).sqrt()
+        };
+        self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&a, &b| bound(a).total_cmp(&bound(b)))
+            .unwrap_or(node)
+    }
+
+    fn visits(&self) -> Vec<Visits> {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|&child| {
+                let child = &self.nodes[child];
+                Visits {
+                    cell: child.cell,
+                    visits: child.visits,
+                    score: child.score,
+                }
+            })
+            .collect()
+    }
+}
+
+// Searches the position with up to the given number of playouts, stopping early once the
+// budget runs out. Every thread grows a tree of its own and their visits are added up
+// at the end, so the threads never wait for each other.
+pub fn search(grid: &Grid, playouts: usize, budget: Duration, threads: usize) -> Vec<Visits> {
+    let deadline = Instant::now().checked_add(budget);
+    let threads = threads.clamp(1, playouts.max(1));
+    let trees: Vec<Vec<Visits>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                // the first threads take the playouts that don't divide evenly
+                let share = playouts / threads + usize::from(worker < playouts % threads);
+                scope.spawn(move || {
+                    let mut rng = StdRng::from_entropy();
+                    let mut tree = Tree::new(grid, &mut rng);
+                    for _ in 0..share.max(1) {
+                        tree.playout(&mut rng);
+                        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                            break;
+                        }
+                    }
+                    tree.visits()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().ok())
+            .collect()
+    });
+    let mut total: Vec<Visits> = Vec::new();
+    for visits in trees.into_iter().flatten() {
+        match total.iter_mut().find(|other| other.cell == visits.cell) {
+            Some(other) => {
+                other.visits += visits.visits;
+                other.score += visits.score;
+            }
+            None => total.push(visits),
+        }
+    }
+    total
+}
+
+// The most visited move, which UCT ends up playing out the most when it is the best
+pub fn best_move(visits: &[Visits]) -> Option<usize> {
+    visits
+        .iter()
+        .max_by_key(|visits| visits.visits)
+        .map(|visits| visits.cell)
+}
+
+// Plays the move Monte Carlo tree search likes best, thinking until it has played the
+// given number of games or its time runs out
+pub struct Mcts {
+    playouts: usize,
+    threads: usize,
+    remark: Option<String>,
+}
+
+impl Mcts {
+    // Searches on every core
+    pub fn new(playouts: usize) -> Mcts {
+        Mcts {
+            playouts: playouts.max(1),
+            threads: thread::available_parallelism().map_or(4, |n| n.get()),
+            remark: None,
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Mcts {
+        self.threads = threads.max(1);
+        self
+    }
+}
+
+impl Opponent for Mcts {
+    fn name(&self) -> String {
+        "MCTS computer".to_string()
+    }
+
+    fn choose(&mut self, board: &Board, budget: Duration) -> Result<(usize, usize), String> {
+        let visits = search(
+            &Grid::from_board(board),
+            self.playouts,
+            budget,
+            self.threads,
+        );
+        let cell = best_move(&visits).ok_or_else(|| "There are no empty cells left".to_string())?;
+        let played = visits.iter().find(|visits| visits.cell == cell);
+        self.remark = played.map(|played| {
+            let games: u32 = visits.iter().map(|visits| visits.visits).sum();
+            format!(
+                "Played out {} games and scored {:.0}% in the ones starting with this move",
+                games,
+                played.win_rate() * 100.0
+            )
+        });
+        Ok((cell / 3, cell % 3))
+    }
+
+    fn remark(&self) -> Option<String> {
+        self.remark.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{parse_cell, parse_position};
+
+    fn choose(position: &str) -> (usize, usize) {
+        Mcts::new(5000)
+            .threads(2)
+            .choose(&parse_position(position).unwrap(), Duration::from_secs(10))
+            .unwrap()
+    }
+
+    #[test]
+    fn takes_a_win() {
+        assert_eq!(choose("xx./oo./..."), parse_cell("c1").unwrap());
+    }
+
+    #[test]
+    fn blocks_a_win() {
+        assert_eq!(choose("xx./o../... o"), parse_cell("c1").unwrap());
+    }
+
+    #[test]
+    fn adds_up_the_threads() {
+        let visits = search(&Grid::new(3), 20000, Duration::from_secs(60), 4);
+        assert_eq!(visits.len(), 9);
+        assert_eq!(
+            visits.iter().map(|visits| visits.visits).sum::<u32>(),
+            20000
+        );
+        // the centre is the strongest opening
+        assert_eq!(best_move(&visits), Some(4));
+    }
+
+    #[test]
+    fn searches_bigger_boards() {
+        // X has three in a row on the top row of a 4x4 board and only needs the fourth
+        let mut grid = Grid::new(4);
+        for cell in [0, 4, 1, 5, 2, 9] {
+            grid = grid.play(cell);
+        }
+        let visits = search(&grid, 2000, Duration::from_secs(10), 1);
+        assert_eq!(best_move(&visits), Some(3));
+    }
+
+    #[test]
+    fn stops_when_the_time_runs_out() {
+        let visits = search(&Grid::new(4), usize::MAX, Duration::from_millis(50), 2);
+        assert!(!visits.is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+// Text formats for positions and whole games, see the Notation section of the README
+use std::{fmt, str::FromStr};
+
+use crate::board::{Board, Mark};
+
+// Cells are named by column a-c from left to right and row 1-3 from top to bottom
+pub fn cell_name((row, col): (usize, usize)) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+pub fn parse_cell(s: &str) -> Result<(usize, usize), String> {
+    match s.as_bytes() {
+        [col @ b'a'..=b'c', row @ b'1'..=b'3'] => {
+            Ok(((row - b'1') as usize, (col - b'a') as usize))
+        }
+        _ => Err(format!("Unknown cell {:?}, expected a1 to c3", s)),
+    }
+}
+
+// The board followed by the side to move, e.g. "x.o/.x./... o"
+pub fn position(board: &Board, to_move: Mark) -> String {
+    format!("{} {}", board, to_move.to_string().to_lowercase())
+}
+
+// Accepts a position with or without the side to move, which has to match the board
+pub fn parse_position(s: &str) -> Result<Board, String> {
+    let mut parts = s.split_whitespace();
+    let board = parts.next().unwrap_or_default().parse::<Board>()?;
+    let to_move = board.validate()?;
+    match parts.next() {
+        None => {}
+        Some("x" | "X") if to_move == Mark::X => {}
+        Some("o" | "O") if to_move == Mark::O => {}
+        Some("x" | "X" | "o" | "O") => {
+            return Err(format!(
+                "X moves first, so it is {}'s turn in {}",
+                to_move, board
+            ))
+        }
+        Some(side) => return Err(format!("Unknown side to move {:?}, expected x or o", side)),
+    }
+    match parts.next() {
+        Some(extra) => Err(format!("Unexpected {:?} after the position", extra)),
+        None => Ok(board),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Mark),
+    Draw,
+    Unfinished,
+}
+
+impl Outcome {
+    // The outcome a position has reached
+    pub fn of(board: &Board) -> Outcome {
+        match board.winner() {
+            Some(mark) => Outcome::Win(mark),
+            None if board.is_full() => Outcome::Draw,
+            None => Outcome::Unfinished,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Win(Mark::X) => write!(f, "1-0"),
+            Outcome::Win(Mark::O) => write!(f, "0-1"),
+            Outcome::Draw => write!(f, "1/2-1/2"),
+            Outcome::Unfinished => write!(f, "*"),
+        }
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Outcome, String> {
+        match s {
+            "1-0" => Ok(Outcome::Win(Mark::X)),
+            "0-1" => Ok(Outcome::Win(Mark::O)),
+            "1/2-1/2" => Ok(Outcome::Draw),
+            "*" => Ok(Outcome::Unfinished),
+            _ => Err(format!("Unknown result {:?}", s)),
+        }
+    }
+}
+
+// A game as headers, the starting position and the moves played from it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game {
+    pub headers: Vec<(String, String)>,
+    pub start: Board,
+    pub moves: Vec<(usize, usize)>,
+    pub outcome: Outcome,
+}
+
+impl Game {
+    // The numbered moves followed by the result, as in the last line of the notation
+    pub fn movetext(&self) -> String {
+        let first = self.start.turn();
+        let mut movetext = Vec::new();
+        for (i, &cell) in self.moves.iter().enumerate() {
+            // number the moves in pairs starting with X, like chess notation
+            let ply = i + if first == Mark::O { 1 } else { 0 };
+            if ply % 2 == 0 {
+                movetext.push(format!("{}.", ply / 2 + 1));
+            } else if i == 0 {
+                movetext.push("1...".to_string());
+            }
+            movetext.push(cell_name(cell));
+        }
+        movetext.push(self.outcome.to_string());
+        movetext.join(" ")
+    }
+
+    // Plays the moves on the starting position, checking each one is legal
+    pub fn boards(&self) -> Result<Vec<Board>, String> {
+        let boards = self.play()?;
+        let reached = Outcome::of(boards.last().unwrap());
+        if self.outcome != reached {
+            return Err(format!(
+                "The result is {} but the final position is {}",
+                self.outcome, reached
+            ));
+        }
+        Ok(boards)
+    }
+
+    fn play(&self) -> Result<Vec<Board>, String> {
+        let mut board = self.start;
+        let mut to_move = board.validate()?;
+        let mut boards = vec![board];
+        for &cell in &self.moves {
+            if board.winner().is_some() || board.is_full() {
+                return Err(format!(
+                    "{} is played after the game is over",
+                    cell_name(cell)
+                ));
+            }
+            if board.get(cell).is_some() {
+                return Err(format!("{} is already taken", cell_name(cell)));
+            }
+            board.set(cell, Some(to_move));
+            to_move = to_move.other();
+            boards.push(board);
+        }
+        Ok(boards)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in &self.headers {
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        let first = self.start.turn();
+        if self.start != Board::default() {
+            writeln!(f, "[Position \"{}\"]", position(&self.start, first))?;
+        }
+        writeln!(f, "[Result \"{}\"]", self.outcome)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.movetext())
+    }
+}
+
+impl FromStr for Game {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Game, String> {
+        let mut game = Game {
+            headers: Vec::new(),
+            start: Board::default(),
+            moves: Vec::new(),
+            outcome: Outcome::Unfinished,
+        };
+        let mut outcome = None;
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(header) = line.strip_prefix('[') {
+                let (name, value) = header
+                    .strip_suffix(']')
+                    .and_then(|header| header.split_once(' '))
+                    .ok_or_else(|| format!("Malformed header {:?}", line))?;
+                let value = value.trim().trim_matches('"');
+                match name {
+                    "Position" => game.start = parse_position(value)?,
+                    "Result" => outcome = Some(value.parse()?),
+                    _ => game.headers.push((name.to_string(), value.to_string())),
+                }
+                continue;
+            }
+            for token in line.split_whitespace() {
+                if token.ends_with('.') {
+                    continue;
+                }
+                match token.parse::<Outcome>() {
+                    Ok(result) if outcome.is_some_and(|outcome| outcome != result) => {
+                        return Err(format!(
+                            "The moves end in {} but the result is {}",
+                            result,
+                            outcome.unwrap()
+                        ))
+                    }
+                    Ok(result) => outcome = Some(result),
+                    Err(_) => game.moves.push(parse_cell(token)?),
+                }
+            }
+        }
+        game.outcome = match outcome {
+            Some(outcome) => outcome,
+            // without a result, take the one the moves reach
+            None => Outcome::of(game.play()?.last().unwrap()),
+        };
+        game.boards()?;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(start: &str, moves: &[&str], outcome: Outcome) -> Game {
+        Game {
+            headers: vec![("Player1".to_string(), "Human".to_string())],
+            start: parse_position(start).unwrap(),
+            moves: moves.iter().map(|cell| parse_cell(cell).unwrap()).collect(),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn cells_round_trip() {
+        for row in 0..3 {
+            for col in 0..3 {
+                assert_eq!(parse_cell(&cell_name((row, col))), Ok((row, col)));
+            }
+        }
+        assert_eq!(cell_name((0, 0)), "a1");
+        assert_eq!(cell_name((1, 1)), "b2");
+        assert!(parse_cell("d1").is_err());
+        assert!(parse_cell("a4").is_err());
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        for text in [".../.../... x", "x.o/.x./... o", "xo./.x./o.. x"] {
+            let board = parse_position(text).unwrap();
+            assert_eq!(position(&board, board.turn()), text);
+            assert_eq!(board.to_string().parse::<Board>(), Ok(board));
+        }
+        // the side to move is optional
+        assert_eq!(
+            parse_position("x.o/.x./..."),
+            parse_position("x.o/.x./... o")
+        );
+    }
+
+    #[test]
+    fn positions_are_checked() {
+        // X moves first, so it can't be X's turn with one more cross
+        assert!(parse_position("x.o/.x./... x").is_err());
+        assert!(parse_position("x../.../... o").is_ok());
+        assert!(parse_position("x../.../... z").is_err());
+        assert!(parse_position("o../.../...").is_err());
+        assert!(parse_position("xxx/oo./...").is_err());
+        assert!(parse_position("x../.../... o extra").is_err());
+    }
+
+    #[test]
+    fn games_round_trip() {
+        let from_empty = game(
+            ".../.../...",
+            &["b2", "a1", "c3", "a3", "a2"],
+            Outcome::Unfinished,
+        );
+        let text = from_empty.to_string();
+        assert!(text.contains("1. b2 a1 2. c3 a3 3. a2 *"));
+        assert!(!text.contains("Position"));
+        assert_eq!(text.parse::<Game>(), Ok(from_empty));
+
+        let o_to_move = game("x.o/.x./...", &["c3", "a3", "a2"], Outcome::Unfinished);
+        let text = o_to_move.to_string();
+        assert!(text.contains("[Position \"x.o/.x./... o\"]"));
+        assert!(text.contains("1... c3 2. a3 a2 *"));
+        assert_eq!(text.parse::<Game>(), Ok(o_to_move));
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let taken = game(".../.../...", &["b2", "b2"], Outcome::Unfinished);
+        assert!(taken.boards().is_err());
+        let after_the_end = game("xx./oo./...", &["c1", "c2"], Outcome::Win(Mark::X));
+        assert!(after_the_end.boards().is_err());
+        assert!("1. b2 b2 *".parse::<Game>().is_err());
+
+        let wrong_result = game("xx./oo./...", &["c1"], Outcome::Draw);
+        assert!(wrong_result.boards().is_err());
+        let not_over = game(".../.../...", &["b2"], Outcome::Win(Mark::X));
+        assert!(not_over.boards().is_err());
+        assert!("[Result \"0-1\"]\n\n1. b2".parse::<Game>().is_err());
+        assert!("[Result \"0-1\"]\n\n1. b2 *".parse::<Game>().is_err());
+        assert!("[Position \"xx./oo./...\"]\n\n1. c1 1/2-1/2"
+            .parse::<Game>()
+            .is_err());
+        let finished = "[Position \"xx./oo./...\"]\n\n1. c1".parse::<Game>();
+        assert_eq!(finished.map(|game| game.outcome), Ok(Outcome::Win(Mark::X)));
+
+        let legal = game(".../.../...", &["b2", "a1"], Outcome::Unfinished);
+        let boards = legal.boards().unwrap();
+        assert_eq!(boards.len(), 3);
+        assert_eq!(boards[2].to_string(), "o../.x./...");
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tictactoe_library::{
+    app::{App, AppState},
+    game::GameState,
+};
+
+use crate::{
+    autopilot,
+    board::{Board, Mark},
+};
+
+// Anything that can pick moves for one side of the board
+pub trait Opponent: Send {
+    fn name(&self) -> String;
+
+    // Picks an empty cell for the side to move, taking no longer than the budget
+    fn choose(&mut self, board: &Board, budget: Duration) -> Result<(usize, usize), String>;
+
+    // Something to say about the move it just chose
+    fn remark(&self) -> Option<String> {
+        None
+    }
+}
+
+// A move and what the opponent said about it
+type Answer = ((usize, usize), Option<String>);
+
+struct Request {
+    board: Board,
+    started: Instant,
+    result: Receiver<Result<Answer, String>>,
+}
+
+// Plays one side of the game for an opponent, which thinks on its own thread so the
+// interface stays responsive
+pub struct Seat {
+    pub mark: Mark,
+    name: String,
+    budget: Duration,
+    opponent: Arc<Mutex<Box<dyn Opponent>>>,
+    request: Option<Request>,
+    // the position the opponent last failed on, so it isn't asked again
+    failed: Option<Board>,
+    remark: Option<String>,
+}
+
+impl Seat {
+    pub fn new(opponent: Box<dyn Opponent>, mark: Mark, budget: Duration) -> Seat {
+        Seat {
+            mark,
+            name: opponent.name(),
+            budget,
+            opponent: Arc::new(Mutex::new(opponent)),
+            request: None,
+            failed: None,
+            remark: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Shows the opponent under another name, like the menu entry it was picked from
+    pub fn named(mut self, name: &str) -> Seat {
+        self.name = name.to_string();
+        self
+    }
+
+    // How long the opponent has been thinking about the current position
+    pub fn thinking(&self) -> Option<Duration> {
+        self.request
+            .as_ref()
+            .map(|request| request.started.elapsed())
+    }
+
+    // What the opponent said about its last move in this game
+    pub fn remark(&self) -> Option<&str> {
+        self.remark.as_deref()
+    }
+
+    // Whether the opponent is the one who should move, so the keyboard shouldn't
+    pub fn to_play(&self, app: &App) -> bool {
+        match (&app.state, Board::of(app)) {
+            (AppState::Playing(GameState::GameInProgress(_, player, _)), Some(board)) => {
+                Mark::of(*player) == self.mark && self.failed != Some(board)
+            }
+            _ => false,
+        }
+    }
+
+    // Asks the opponent for a move when it is its turn and plays the answer once it
+    // arrives, returning whether the game changed
+    pub fn poll(&mut self, app: &mut App) -> bool {
+        let board = match Board::of(app) {
+            Some(board) if self.to_play(app) => board,
+            board => {
+                // the game moved on without the answer
+                self.request = None;
+                // and nothing has been said yet in a new game
+                if board.unwrap_or_default() == Board::default() {
+                    self.remark = None;
+                }
+                return false;
+            }
+        };
+        let request = match &self.request {
+            Some(request) if request.board == board => request,
+            _ => {
+                self.request = Some(self.ask(board));
+                return false;
+            }
+        };
+        let answer = match request.result.try_recv() {
+            Ok(answer) => answer,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => Err(format!("{} stopped responding", self.name)),
+        };
+        self.request = None;
+        let played = answer.and_then(|(cell, remark)| match board.get(cell) {
+            None => {
+                self.remark = remark;
+                autopilot::place(app, cell)
+            }
+            Some(_) => Err(format!("{} tried to play on a taken cell", self.name)),
+        });
+        if let Err(error) = played {
+            self.failed = Some(board);
+            app.warning_message = Some(format!(
+                "{}\nMake a move for {} to carry on.",
+                error, self.mark
+            ));
+        }
+        true
+    }
+
+    fn ask(&self, board: Board) -> Request {
+        let (tx, rx) = mpsc::channel();
+        let opponent = Arc::clone(&self.opponent);
+        let budget = self.budget;
+        thread::spawn(move || {
+            let answer = match opponent.lock() {
+                Ok(mut opponent) => opponent
+                    .choose(&board, budget)
+                    .map(|cell| (cell, opponent.remark())),
+                Err(_) => Err("The opponent crashed".to_string()),
+            };
+            // nobody is listening any more if the game moved on
+            let _ = tx.send(answer);
+        });
+        Request {
+            board,
+            started: Instant::now(),
+            result: rx,
+        }
+    }
+}
@@ -0,0 +1,347 @@
+// The line based protocol between clients and the lobby server, see the Server section
+// of the README
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    str::FromStr,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    board::Mark,
+    notation::{cell_name, parse_cell, Outcome},
+};
+
+// Client to server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Request {
+    Name(String),
+    List,
+    Create,
+    Join(u32),
+    Move((usize, usize)),
+    // play another game in the same room once both players have asked
+    Again,
+    Leave,
+    // a chat message for the opponent
+    Say(String),
+    // follow the game in a room without playing
+    Watch(u32),
+}
+
+// Server to client
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reply {
+    // one line per room after a list request, followed by End
+    Room {
+        id: u32,
+        open: bool,
+        players: String,
+    },
+    End,
+    Created(u32),
+    Start {
+        room: u32,
+        mark: Mark,
+        opponent: String,
+    },
+    // the opponent's move
+    Moved((usize, usize)),
+    Over(Outcome),
+    // the opponent left the room
+    Left,
+    // a chat message from the opponent
+    Said(String),
+    // the game being watched, sent again whenever a new game starts in the room or a
+    // player leaves, followed by the moves so far, Over if it has ended and the seats
+    Watching(u32),
+    // a player of the watched game and how long they have thought in it, with an
+    // empty name while the seat is free
+    Seat {
+        mark: Mark,
+        clock: Duration,
+        name: String,
+    },
+    // both players left the watched room
+    Closed,
+    Error(String),
+}
+
+// Names go last on a line, so they are the only part that can have spaces
+pub fn clean_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Everything on the line after the command
+fn rest(line: &str, command: &str) -> String {
+    line.trim()[command.len()..].trim().to_string()
+}
+
+fn parse_mark(s: &str) -> Result<Mark, String> {
+    match s {
+        "x" => Ok(Mark::X),
+        "o" => Ok(Mark::O),
+        _ => Err(format!("Unknown mark {:?}", s)),
+    }
+}
+
+fn parse_id(s: &str) -> Result<u32, String> {
+    s.parse().map_err(|_| format!("Unknown room {:?}", s))
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Request::Name(name) => write!(f, "name {}", name),
+            Request::List => write!(f, "list"),
+            Request::Create => write!(f, "create"),
+            Request::Join(id) => write!(f, "join {}", id),
+            Request::Move(cell) => write!(f, "move {}", cell_name(*cell)),
+            Request::Again => write!(f, "again"),
+            Request::Leave => write!(f, "leave"),
+            Request::Say(text) => write!(f, "say {}", text),
+            Request::Watch(id) => write!(f, "watch {}", id),
+        }
+    }
+}
+
+impl FromStr for Request {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Request, String> {
+        let (command, rest) = s.trim().split_once(' ').unwrap_or((s.trim(), ""));
+        match command {
+            "name" if !clean_name(rest).is_empty() => Ok(Request::Name(clean_name(rest))),
+            "list" => Ok(Request::List),
+            "create" => Ok(Request::Create),
+            "join" => Ok(Request::Join(parse_id(rest.trim())?)),
+            "move" => Ok(Request::Move(parse_cell(rest.trim())?)),
+            "again" => Ok(Request::Again),
+            "leave" => Ok(Request::Leave),
+            "say" if !rest.trim().is_empty() => Ok(Request::Say(rest.trim().to_string())),
+            "watch" => Ok(Request::Watch(parse_id(rest.trim())?)),
+            _ => Err(format!("Unknown request {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reply::Room { id, open, players } => {
+                let state = if *open { "open" } else { "full" };
+                write!(f, "room {} {} {}", id, state, players)
+            }
+            Reply::End => write!(f, "end"),
+            Reply::Created(id) => write!(f, "created {}", id),
+            Reply::Start {
+                room,
+                mark,
+                opponent,
+            } => write!(
+                f,
+                "start {} {} {}",
+                room,
+                mark.to_string().to_lowercase(),
+                opponent
+            ),
+            Reply::Moved(cell) => write!(f, "moved {}", cell_name(*cell)),
+            Reply::Over(outcome) => write!(f, "over {}", outcome),
+            Reply::Left => write!(f, "left"),
+            Reply::Said(text) => write!(f, "said {}", text),
+            Reply::Watching(id) => write!(f, "watching {}", id),
+            Reply::Seat { mark, clock, name } => write!(
+                f,
+                "seat {} {} {}",
+                mark.to_string().to_lowercase(),
+                clock.as_millis(),
+                name
+            ),
+            Reply::Closed => write!(f, "closed"),
+            Reply::Error(message) => write!(f, "error {}", message),
+        }
+    }
+}
+
+impl FromStr for Reply {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Reply, String> {
+        let mut words = s.trim().splitn(4, ' ');
+        let mut word = || words.next().unwrap_or_default();
+        match word() {
+            "room" => Ok(Reply::Room {
+                id: parse_id(word())?,
+                open: word() == "open",
+                players: word().to_string(),
+            }),
+            "end" => Ok(Reply::End),
+            "created" => Ok(Reply::Created(parse_id(word())?)),
+            "start" => Ok(Reply::Start {
+                room: parse_id(word())?,
+                mark: parse_mark(word())?,
+                opponent: word().to_string(),
+            }),
+            "moved" => Ok(Reply::Moved(parse_cell(word())?)),
+            "over" => Ok(Reply::Over(word().parse()?)),
+            "left" => Ok(Reply::Left),
+            "said" => Ok(Reply::Said(rest(s, "said"))),
+            "watching" => Ok(Reply::Watching(parse_id(word())?)),
+            "seat" => Ok(Reply::Seat {
+                mark: parse_mark(word())?,
+                clock: Duration::from_millis(
+                    word()
+                        .parse()
+                        .map_err(|_| format!("Malformed seat {:?}", s))?,
+                ),
+                name: word().to_string(),
+            }),
+            "closed" => Ok(Reply::Closed),
+            "error" => Ok(Reply::Error(rest(s, "error"))),
+            _ => Err(format!("Unknown reply {:?}", s)),
+        }
+    }
+}
+
+// A client's connection to the lobby server
+pub struct Connection {
+    stream: TcpStream,
+    replies: Receiver<String>,
+}
+
+impl Connection {
+    pub fn open(address: &str) -> Result<Connection, String> {
+        let stream = TcpStream::connect(address)
+            .map_err(|err| format!("Could not connect to {}: {}", address, err))?;
+        let reader = stream
+            .try_clone()
+            .map_err(|err| format!("Could not read from {}: {}", address, err))?;
+
+        let (tx, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Connection { stream, replies })
+    }
+
+    pub fn send(&mut self, request: &Request) -> Result<(), String> {
+        writeln!(self.stream, "{}", request)
+            .and_then(|_| self.stream.flush())
+            .map_err(|_| "Lost the connection to the server".to_string())
+    }
+
+    // Everything the server sent since the last call, skipping lines this client
+    // doesn't understand
+    pub fn poll(&self) -> Result<Vec<Reply>, String> {
+        let mut replies = Vec::new();
+        loop {
+            match self.replies.try_recv() {
+                Ok(line) => replies.extend(line.parse().ok()),
+                Err(TryRecvError::Empty) => return Ok(replies),
+                Err(TryRecvError::Disconnected) if replies.is_empty() => {
+                    return Err("Lost the connection to the server".to_string())
+                }
+                // hand over what arrived before the server hung up first
+                Err(TryRecvError::Disconnected) => return Ok(replies),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip_request(request: Request) {
+        assert_eq!(request.to_string().parse(), Ok(request));
+    }
+
+    fn round_trip_reply(reply: Reply) {
+        assert_eq!(reply.to_string().parse(), Ok(reply));
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        round_trip_request(Request::Name("Bob Smith".to_string()));
+        round_trip_request(Request::List);
+        round_trip_request(Request::Create);
+        round_trip_request(Request::Join(12));
+        round_trip_request(Request::Move(parse_cell("c2").unwrap()));
+        round_trip_request(Request::Again);
+        round_trip_request(Request::Leave);
+        round_trip_request(Request::Say("good luck, have fun!".to_string()));
+        round_trip_request(Request::Watch(3));
+    }
+
+    #[test]
+    fn replies_round_trip() {
+        round_trip_reply(Reply::Room {
+            id: 1,
+            open: false,
+            players: "Alice (X) against Bob Smith (O)".to_string(),
+        });
+        round_trip_reply(Reply::End);
+        round_trip_reply(Reply::Created(7));
+        round_trip_reply(Reply::Start {
+            room: 7,
+            mark: Mark::O,
+            opponent: "Mary Ann Jones".to_string(),
+        });
+        round_trip_reply(Reply::Moved(parse_cell("a3").unwrap()));
+        round_trip_reply(Reply::Over(Outcome::Win(Mark::X)));
+        round_trip_reply(Reply::Over(Outcome::Draw));
+        round_trip_reply(Reply::Left);
+        round_trip_reply(Reply::Said("well played  :)".to_string()));
+        round_trip_reply(Reply::Watching(7));
+        round_trip_reply(Reply::Seat {
+            mark: Mark::X,
+            clock: Duration::from_millis(61_250),
+            name: "Bob Smith".to_string(),
+        });
+        round_trip_reply(Reply::Seat {
+            mark: Mark::O,
+            clock: Duration::ZERO,
+            name: String::new(),
+        });
+        round_trip_reply(Reply::Closed);
+        round_trip_reply(Reply::Error("Room 7 is full".to_string()));
+    }
+
+    #[test]
+    fn names_are_cleaned_up() {
+        assert_eq!(clean_name("  Bob \t Smith "), "Bob Smith");
+        assert_eq!(
+            "name   Bob   Smith ".parse(),
+            Ok(Request::Name("Bob Smith".to_string()))
+        );
+        assert!("name".parse::<Request>().is_err());
+        assert!("name   ".parse::<Request>().is_err());
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        assert_eq!(
+            "start 1 z Bob".parse::<Reply>(),
+            Err("Unknown mark \"z\"".to_string())
+        );
+        assert_eq!(
+            "join one".parse::<Request>(),
+            Err("Unknown room \"one\"".to_string())
+        );
+        assert!("watching".parse::<Reply>().is_err());
+        assert!("move d4".parse::<Request>().is_err());
+        assert!("moved a".parse::<Reply>().is_err());
+        assert!("seat x soon Bob".parse::<Reply>().is_err());
+        assert!("over 2-0".parse::<Reply>().is_err());
+        assert!("say".parse::<Request>().is_err());
+        assert!("hello".parse::<Request>().is_err());
+        assert!("hello".parse::<Reply>().is_err());
+    }
+}
@@ -0,0 +1,768 @@
+// Keeps a lobby of rooms for clients to play each other in, passing moves between them
+// after checking them against the rules
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    process,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tictactoe_tui::{
+    board::{Board, Mark},
+    notation::{cell_name, Outcome},
+    protocol::{Reply, Request},
+};
+
+const USAGE: &str = "\
+usage: tic-tac-toe-server [--address <host:port>]
+
+Listens on 127.0.0.1:7878 unless another address is given, e.g. 0.0.0.0:7878 to
+let other machines on the network connect.";
+
+// longest chat message passed on, in characters
+const MAX_MESSAGE: usize = 200;
+
+struct Client {
+    name: String,
+    tx: Sender<Reply>,
+    room: Option<u32>,
+}
+
+struct Room {
+    // the clients playing X and O
+    players: [Option<usize>; 2],
+    board: Board,
+    // the moves of the game so far, for clients that start watching halfway
+    moves: Vec<(usize, usize)>,
+    playing: bool,
+    // who asked to play again after the last game
+    again: [bool; 2],
+    // the clients following the game without playing
+    watchers: Vec<usize>,
+    // how long X and O have thought in this game, up to when the turn started
+    clocks: [Duration; 2],
+    turn_started: Instant,
+}
+
+impl Room {
+    fn new(player: usize) -> Room {
+        Room {
+            players: [Some(player), None],
+            board: Board::default(),
+            moves: Vec::new(),
+            playing: false,
+            again: [false; 2],
+            watchers: Vec::new(),
+            clocks: [Duration::ZERO; 2],
+            turn_started: Instant::now(),
+        }
+    }
+
+    fn seat(&self, client: usize) -> Result<usize, String> {
+        match self
+            .players
+            .iter()
+            .position(|&player| player == Some(client))
+        {
+            Some(seat) => Ok(seat),
+            None if self.watchers.contains(&client) => Err("You are only watching".to_string()),
+            None => Err("You are not in a room".to_string()),
+        }
+    }
+
+    // How long the seat has thought in this game, counting the turn still going on
+    fn clock(&self, seat: usize) -> Duration {
+        if self.playing && self.board.turn() == mark(seat) {
+            self.clocks[seat] + self.turn_started.elapsed()
+        } else {
+            self.clocks[seat]
+        }
+    }
+}
+
+#[derive(Default)]
+struct Lobby {
+    clients: HashMap<usize, Client>,
+    rooms: BTreeMap<u32, Room>,
+    next_room: u32,
+}
+
+fn mark(seat: usize) -> Mark {
+    if seat == 0 {
+        Mark::X
+    } else {
+        Mark::O
+    }
+}
+
+impl Lobby {
+    fn send(&self, client: usize, reply: Reply) {
+        if let Some(client) = self.clients.get(&client) {
+            // a client that hung up is removed by its own thread
+            let _ = client.tx.send(reply);
+        }
+    }
+
+    fn name(&self, client: usize) -> String {
+        self.clients
+            .get(&client)
+            .map_or_else(String::new, |client| client.name.clone())
+    }
+
+    fn handle(&mut self, client: usize, request: Request) -> Result<(), String> {
+        let room = self.clients.get(&client).and_then(|client| client.room);
+        match request {
+            Request::Name(name) => {
+                if let Some(client) = self.clients.get_mut(&client) {
+                    client.name = name;
+                }
+            }
+            Request::List => {
+                for (&id, room) in &self.rooms {
+                    let names: Vec<String> = room
+                        .players
+                        .iter()
+                        .flatten()
+                        .map(|&p| self.name(p))
+                        .collect();
+                    let open = room.players.contains(&None);
+                    self.send(
+                        client,
+                        Reply::Room {
+                            id,
+                            open,
+                            players: names.join(" vs "),
+                        },
+                    );
+                }
+                self.send(client, Reply::End);
+            }
+            Request::Create => {
+                if room.is_some() {
+                    return Err("Leave your room before creating another".to_string());
+                }
+                self.next_room += 1;
+                let id = self.next_room;
+                self.rooms.insert(id, Room::new(client));
+                self.enter(client, id);
+                self.send(client, Reply::Created(id));
+            }
+            Request::Join(id) => {
+                if room.is_some() {
+                    return Err("Leave your room before joining another".to_string());
+                }
+                let room = self
+                    .rooms
+                    .get_mut(&id)
+                    .ok_or_else(|| format!("There is no room {}", id))?;
+                let seat = room
+                    .players
+                    .iter()
+                    .position(Option::is_none)
+                    .ok_or_else(|| format!("Room {} is full", id))?;
+                room.players[seat] = Some(client);
+                self.enter(client, id);
+                self.start(id);
+            }
+            Request::Move(cell) => {
+                let id = room.ok_or("You are not in a room")?;
+                let room = self.rooms.get_mut(&id).ok_or("You are not in a room")?;
+                let seat = room.seat(client)?;
+                if !room.playing {
+                    return Err("The game hasn't started".to_string());
+                }
+                if room.board.turn() != mark(seat) {
+                    return Err("It isn't your turn".to_string());
+                }
+                if room.board.get(cell).is_some() {
+                    return Err(format!("{} is already taken", cell_name(cell)));
+                }
+                room.board.set(cell, Some(mark(seat)));
+                room.moves.push(cell);
+                room.clocks[seat] += room.turn_started.elapsed();
+                room.turn_started = Instant::now();
+                let outcome = match room.board.winner() {
+                    Some(winner) => Some(Outcome::Win(winner)),
+                    None if room.board.is_full() => Some(Outcome::Draw),
+                    None => None,
+                };
+                if outcome.is_some() {
+                    room.playing = false;
+                    room.again = [false; 2];
+                }
+                let players = room.players;
+                let watchers = room.watchers.clone();
+                for other in players[1 - seat].iter().chain(&watchers) {
+                    self.send(*other, Reply::Moved(cell));
+                }
+                if let Some(outcome) = outcome {
+                    for client in players.iter().flatten().chain(&watchers) {
+                        self.send(*client, Reply::Over(outcome));
+                    }
+                }
+            }
+            Request::Again => {
+                let id = room.ok_or("You are not in a room")?;
+                let room = self.rooms.get_mut(&id).ok_or("You are not in a room")?;
+                let seat = room.seat(client)?;
+                if room.playing {
+                    return Err("The game is still going".to_string());
+                }
+                room.again[seat] = true;
+                if room.again == [true; 2] {
+                    // take turns going first
+                    room.players.swap(0, 1);
+                    self.start(id);
+                }
+            }
+            Request::Say(text) => {
+                let id = room.ok_or("You are not in a room")?;
+                let room = self.rooms.get(&id).ok_or("You are not in a room")?;
+                let seat = room.seat(client)?;
+                let other = room.players[1 - seat].ok_or("There is nobody to talk to")?;
+                let text: String = text.chars().take(MAX_MESSAGE).collect();
+                self.send(other, Reply::Said(text));
+            }
+            Request::Watch(id) => {
+                if room.is_some() {
+                    return Err("Leave your room before watching another".to_string());
+                }
+                let room = self
+                    .rooms
+                    .get_mut(&id)
+                    .ok_or_else(|| format!("There is no room {}", id))?;
+                room.watchers.push(client);
+                self.enter(client, id);
+                self.sync(id, &[client]);
+            }
+            Request::Leave => self.leave(client),
+        }
+        Ok(())
+    }
+
+    // Sends watchers the game in the room as it stands
+    fn sync(&self, id: u32, watchers: &[usize]) {
+        let room = match self.rooms.get(&id) {
+            Some(room) => room,
+            None => return,
+        };
+        let outcome = match room.board.winner() {
+            Some(winner) => Some(Outcome::Win(winner)),
+            None if room.board.is_full() => Some(Outcome::Draw),
+            None => None,
+        };
+        for &watcher in watchers {
+            self.send(watcher, Reply::Watching(id));
+            for &cell in &room.moves {
+                self.send(watcher, Reply::Moved(cell));
+            }
+            if let Some(outcome) = outcome {
+                self.send(watcher, Reply::Over(outcome));
+            }
+            for (seat, player) in room.players.iter().enumerate() {
+                let seat = Reply::Seat {
+                    mark: mark(seat),
+                    clock: room.clock(seat),
+                    name: player.map_or_else(String::new, |player| self.name(player)),
+                };
+                self.send(watcher, seat);
+            }
+        }
+    }
+
+    fn enter(&mut self, client: usize, id: u32) {
+        if let Some(client) = self.clients.get_mut(&client) {
+            client.room = Some(id);
+        }
+    }
+
+    fn start(&mut self, id: u32) {
+        let (players, watchers) = match self.rooms.get_mut(&id) {
+            Some(room) => {
+                room.board = Board::default();
+                room.moves.clear();
+                room.playing = true;
+                room.again = [false; 2];
+                room.clocks = [Duration::ZERO; 2];
+                room.turn_started = Instant::now();
+                (room.players, room.watchers.clone())
+            }
+            None => return,
+        };
+        if let [Some(x), Some(o)] = players {
+            eprintln!(
+                "room {}: {} (X) against {} (O)",
+                id,
+                self.name(x),
+                self.name(o)
+            );
+            for (seat, (player, opponent)) in [(x, o), (o, x)].into_iter().enumerate() {
+                let reply = Reply::Start {
+                    room: id,
+                    mark: mark(seat),
+                    opponent: self.name(opponent),
+                };
+                self.send(player, reply);
+            }
+        }
+        self.sync(id, &watchers);
+    }
+
+    // Takes the client out of its room, which stays open for the other player
+    fn leave(&mut self, client: usize) {
+        let id = match self
+            .clients
+            .get_mut(&client)
+            .and_then(|client| client.room.take())
+        {
+            Some(id) => id,
+            None => return,
+        };
+        let (others, watchers) = match self.rooms.get_mut(&id) {
+            // nobody needs to know a watcher left
+            Some(room) if room.watchers.contains(&client) => {
+                room.watchers.retain(|&watcher| watcher != client);
+                return;
+            }
+            Some(room) => {
+                if room.playing {
+                    // stop the clock of the side that was thinking
+                    let seat = usize::from(room.board.turn() == Mark::O);
+                    room.clocks[seat] = room.clock(seat);
+                }
+                for player in room.players.iter_mut() {
+                    if *player == Some(client) {
+                        *player = None;
+                    }
+                }
+                room.playing = false;
+                (room.players, room.watchers.clone())
+            }
+            None => return,
+        };
+        match others {
+            [None, None] => {
+                self.rooms.remove(&id);
+                for watcher in watchers {
+                    if let Some(watcher) = self.clients.get_mut(&watcher) {
+                        watcher.room = None;
+                    }
+                    self.send(watcher, Reply::Closed);
+                }
+            }
+            others => {
+                for other in others.into_iter().flatten() {
+                    self.send(other, Reply::Left);
+                }
+                self.sync(id, &watchers);
+            }
+        }
+    }
+}
+
+fn serve(lobby: Arc<Mutex<Lobby>>, id: usize, stream: TcpStream) {
+    let (tx, rx) = mpsc::channel::<Reply>();
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    thread::spawn(move || {
+        for reply in rx {
+            if writeln!(writer, "{}", reply).is_err() {
+                return;
+            }
+        }
+    });
+    lobby
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clients
+        .insert(
+            id,
+            Client {
+                name: format!("Player {}", id),
+                tx,
+                room: None,
+            },
+        );
+    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+        if line.trim() == "quit" {
+            break;
+        }
+        let mut lobby = lobby.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = line.parse().and_then(|request| lobby.handle(id, request));
+        if let Err(error) = result {
+            lobby.send(id, Reply::Error(error));
+        }
+    }
+    let mut lobby = lobby.lock().unwrap_or_else(PoisonError::into_inner);
+    lobby.leave(id);
+    lobby.clients.remove(&id);
+}
+
+fn main() {
+    let mut address = "127.0.0.1:7878".to_string();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--address", Some(value)) => address = value,
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Could not listen on {}: {}", address, err);
+            process::exit(1);
+        }
+    };
+    eprintln!("Listening on {}", address);
+
+    let lobby = Arc::new(Mutex::new(Lobby::default()));
+    for (id, stream) in listener.incoming().enumerate() {
+        if let Ok(stream) = stream {
+            let lobby = Arc::clone(&lobby);
+            thread::spawn(move || serve(lobby, id + 1, stream));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::Receiver;
+
+    use tictactoe_tui::notation::parse_cell;
+
+    use super::*;
+
+    // A lobby with the clients Alice and Bob, and what the server sends each of them
+    fn lobby() -> (Lobby, [Receiver<Reply>; 2]) {
+        let mut lobby = Lobby::default();
+        let receivers = [1, 2].map(|id| {
+            let (tx, rx) = mpsc::channel();
+            let name = ["Alice", "Bob"][id - 1].to_string();
+            lobby.clients.insert(
+                id,
+                Client {
+                    name,
+                    tx,
+                    room: None,
+                },
+            );
+            rx
+        });
+        (lobby, receivers)
+    }
+
+    fn replies(rx: &Receiver<Reply>) -> Vec<Reply> {
+        rx.try_iter().collect()
+    }
+
+    // Alice creates room 1 and Bob joins it
+    fn playing() -> (Lobby, [Receiver<Reply>; 2]) {
+        let (mut lobby, rx) = lobby();
+        lobby.handle(1, Request::Create).unwrap();
+        lobby.handle(2, Request::Join(1)).unwrap();
+        rx.iter().for_each(|rx| drop(replies(rx)));
+        (lobby, rx)
+    }
+
+    // Carol, who only watches
+    fn carol(lobby: &mut Lobby) -> Receiver<Reply> {
+        let (tx, rx) = mpsc::channel();
+        let carol = Client {
+            name: "Carol".to_string(),
+            tx,
+            room: None,
+        };
+        lobby.clients.insert(3, carol);
+        rx
+    }
+
+    fn play(lobby: &mut Lobby, moves: &[(usize, &str)]) {
+        for &(client, cell) in moves {
+            let cell = parse_cell(cell).unwrap();
+            lobby.handle(client, Request::Move(cell)).unwrap();
+        }
+    }
+
+    #[test]
+    fn join_starts_a_game() {
+        let (mut lobby, [alice, bob]) = lobby();
+        lobby.handle(1, Request::Create).unwrap();
+        assert_eq!(replies(&alice), [Reply::Created(1)]);
+
+        lobby.handle(2, Request::List).unwrap();
+        let room = Reply::Room {
+            id: 1,
+            open: true,
+            players: "Alice".to_string(),
+        };
+        assert_eq!(replies(&bob), [room, Reply::End]);
+
+        lobby.handle(2, Request::Join(1)).unwrap();
+        let start = |mark, opponent: &str| Reply::Start {
+            room: 1,
+            mark,
+            opponent: opponent.to_string(),
+        };
+        assert_eq!(replies(&alice), [start(Mark::X, "Bob")]);
+        assert_eq!(replies(&bob), [start(Mark::O, "Alice")]);
+
+        assert!(lobby.handle(2, Request::Create).is_err());
+        assert!(lobby.handle(2, Request::Join(2)).is_err());
+    }
+
+    #[test]
+    fn full_and_missing_rooms() {
+        let (mut lobby, _rx) = playing();
+        let (tx, _carol) = mpsc::channel();
+        let carol = Client {
+            name: "Carol".to_string(),
+            tx,
+            room: None,
+        };
+        lobby.clients.insert(3, carol);
+        assert_eq!(
+            lobby.handle(3, Request::Join(1)),
+            Err("Room 1 is full".to_string())
+        );
+        assert!(lobby.handle(3, Request::Join(7)).is_err());
+    }
+
+    #[test]
+    fn moves_are_checked() {
+        let (mut lobby, [alice, bob]) = playing();
+        let b2 = parse_cell("b2").unwrap();
+        assert_eq!(
+            lobby.handle(2, Request::Move(b2)),
+            Err("It isn't your turn".to_string())
+        );
+        lobby.handle(1, Request::Move(b2)).unwrap();
+        assert_eq!(replies(&bob), [Reply::Moved(b2)]);
+        assert!(replies(&alice).is_empty());
+        assert_eq!(
+            lobby.handle(1, Request::Move(b2)),
+            Err("It isn't your turn".to_string())
+        );
+        assert_eq!(
+            lobby.handle(2, Request::Move(b2)),
+            Err("b2 is already taken".to_string())
+        );
+    }
+
+    #[test]
+    fn wins_and_draws_end_the_game() {
+        let (mut lobby, [alice, bob]) = playing();
+        play(
+            &mut lobby,
+            &[(1, "a1"), (2, "a2"), (1, "b1"), (2, "b2"), (1, "c1")],
+        );
+        let over = Reply::Over(Outcome::Win(Mark::X));
+        assert_eq!(replies(&alice).last(), Some(&over));
+        assert_eq!(replies(&bob).last(), Some(&over));
+        let c3 = parse_cell("c3").unwrap();
+        assert!(lobby.handle(2, Request::Move(c3)).is_err());
+
+        let (mut lobby, [alice, _]) = playing();
+        play(
+            &mut lobby,
+            &[
+                (1, "b2"),
+                (2, "a1"),
+                (1, "c3"),
+                (2, "a3"),
+                (1, "a2"),
+                (2, "c2"),
+                (1, "b1"),
+                (2, "b3"),
+                (1, "c1"),
+            ],
+        );
+        assert_eq!(replies(&alice).last(), Some(&Reply::Over(Outcome::Draw)));
+    }
+
+    #[test]
+    fn again_swaps_who_goes_first() {
+        let (mut lobby, [alice, bob]) = playing();
+        assert!(lobby.handle(1, Request::Again).is_err());
+        play(
+            &mut lobby,
+            &[(1, "a1"), (2, "a2"), (1, "b1"), (2, "b2"), (1, "c1")],
+        );
+        drop(replies(&alice));
+        drop(replies(&bob));
+
+        lobby.handle(1, Request::Again).unwrap();
+        assert!(replies(&bob).is_empty());
+        lobby.handle(2, Request::Again).unwrap();
+        let start = |mark, opponent: &str| Reply::Start {
+            room: 1,
+            mark,
+            opponent: opponent.to_string(),
+        };
+        assert_eq!(replies(&alice), [start(Mark::O, "Bob")]);
+        assert_eq!(replies(&bob), [start(Mark::X, "Alice")]);
+        let b2 = parse_cell("b2").unwrap();
+        assert!(lobby.handle(1, Request::Move(b2)).is_err());
+        lobby.handle(2, Request::Move(b2)).unwrap();
+    }
+
+    #[test]
+    fn leaving_empties_the_room() {
+        let (mut lobby, [alice, bob]) = playing();
+        lobby.handle(2, Request::Leave).unwrap();
+        assert_eq!(replies(&alice), [Reply::Left]);
+        assert!(replies(&bob).is_empty());
+        // the room stays open for someone else
+        lobby.handle(2, Request::List).unwrap();
+        let room = Reply::Room {
+            id: 1,
+            open: true,
+            players: "Alice".to_string(),
+        };
+        assert_eq!(replies(&bob), [room, Reply::End]);
+
+        lobby.handle(1, Request::Leave).unwrap();
+        assert!(lobby.rooms.is_empty());
+        lobby.handle(2, Request::List).unwrap();
+        assert_eq!(replies(&bob), [Reply::End]);
+    }
+
+    #[test]
+    fn chat_goes_to_the_opponent() {
+        let (mut lobby, [alice, bob]) = playing();
+        lobby.handle(1, Request::Say("gg".to_string())).unwrap();
+        assert_eq!(replies(&bob), [Reply::Said("gg".to_string())]);
+        assert!(replies(&alice).is_empty());
+        lobby.handle(2, Request::Leave).unwrap();
+        assert!(lobby.handle(1, Request::Say("hello?".to_string())).is_err());
+    }
+
+    fn moved(cells: &[&str]) -> Vec<Reply> {
+        cells
+            .iter()
+            .map(|cell| Reply::Moved(parse_cell(cell).unwrap()))
+            .collect()
+    }
+
+    // The players' names in the seat lines at the end of a sync
+    fn seats(sync: &[Reply]) -> Vec<(Mark, String)> {
+        sync.iter()
+            .filter_map(|reply| match reply {
+                Reply::Seat { mark, name, .. } => Some((*mark, name.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn watchers_get_the_game_so_far() {
+        let (mut lobby, _rx) = playing();
+        play(&mut lobby, &[(1, "a1"), (2, "a2")]);
+        let carol = carol(&mut lobby);
+        lobby.handle(3, Request::Watch(1)).unwrap();
+        let sync = replies(&carol);
+        assert_eq!(sync[0], Reply::Watching(1));
+        assert_eq!(sync[1..3], moved(&["a1", "a2"]));
+        assert_eq!(
+            seats(&sync),
+            [(Mark::X, "Alice".to_string()), (Mark::O, "Bob".to_string())]
+        );
+        assert_eq!(sync.len(), 5);
+
+        play(&mut lobby, &[(1, "b1"), (2, "b2"), (1, "c1")]);
+        let mut expected = moved(&["b1", "b2", "c1"]);
+        expected.push(Reply::Over(Outcome::Win(Mark::X)));
+        assert_eq!(replies(&carol), expected);
+
+        // late watchers see how the game ended
+        lobby.handle(3, Request::Leave).unwrap();
+        lobby.handle(3, Request::Watch(1)).unwrap();
+        let sync = replies(&carol);
+        assert_eq!(sync[6], Reply::Over(Outcome::Win(Mark::X)));
+    }
+
+    #[test]
+    fn watchers_only_watch() {
+        let (mut lobby, [alice, bob]) = playing();
+        let carol = carol(&mut lobby);
+        assert!(lobby.handle(3, Request::Watch(7)).is_err());
+        lobby.handle(3, Request::Watch(1)).unwrap();
+        let c1 = parse_cell("c1").unwrap();
+        assert_eq!(
+            lobby.handle(3, Request::Move(c1)),
+            Err("You are only watching".to_string())
+        );
+        assert!(lobby.handle(3, Request::Say("hi".to_string())).is_err());
+        assert!(lobby.handle(3, Request::Again).is_err());
+        assert!(lobby.handle(3, Request::Create).is_err());
+        assert!(lobby.handle(3, Request::Watch(1)).is_err());
+        // the players don't hear about the watcher coming or going
+        lobby.handle(3, Request::Leave).unwrap();
+        assert!(replies(&alice).is_empty());
+        assert!(replies(&bob).is_empty());
+        drop(replies(&carol));
+        play(&mut lobby, &[(1, "b2")]);
+        assert!(replies(&carol).is_empty());
+    }
+
+    #[test]
+    fn watchers_follow_the_room() {
+        let (mut lobby, _rx) = playing();
+        let carol = carol(&mut lobby);
+        lobby.handle(3, Request::Watch(1)).unwrap();
+        play(
+            &mut lobby,
+            &[(1, "a1"), (2, "a2"), (1, "b1"), (2, "b2"), (1, "c1")],
+        );
+        drop(replies(&carol));
+
+        // a new game starts a new sync, with the players swapped
+        lobby.handle(1, Request::Again).unwrap();
+        lobby.handle(2, Request::Again).unwrap();
+        let sync = replies(&carol);
+        assert_eq!(sync[0], Reply::Watching(1));
+        assert_eq!(
+            seats(&sync),
+            [(Mark::X, "Bob".to_string()), (Mark::O, "Alice".to_string())]
+        );
+
+        // a player leaving frees the seat
+        lobby.handle(2, Request::Leave).unwrap();
+        let sync = replies(&carol);
+        assert_eq!(
+            seats(&sync),
+            [(Mark::X, String::new()), (Mark::O, "Alice".to_string())]
+        );
+
+        // and the room closes once both are gone
+        lobby.handle(1, Request::Leave).unwrap();
+        assert_eq!(replies(&carol), [Reply::Closed]);
+        assert!(lobby.rooms.is_empty());
+        lobby.handle(3, Request::Create).unwrap();
+    }
+
+    #[test]
+    fn clocks_count_the_side_to_move() {
+        let (mut lobby, _rx) = playing();
+        let room = lobby.rooms.get_mut(&1).unwrap();
+        room.turn_started -= Duration::from_secs(3);
+        assert!(room.clock(0) >= Duration::from_secs(3));
+        assert_eq!(room.clock(1), Duration::ZERO);
+        play(&mut lobby, &[(1, "b2")]);
+        let room = &lobby.rooms[&1];
+        assert!(room.clock(0) >= Duration::from_secs(3));
+        assert!(room.clock(1) < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,819 @@
+use std::{
+    fs,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tictactoe_library::{
+    app::{App, AppState},
+    game::{Cells, GameCell, GameState, Player},
+    update::Key,
+};
+
+use tictactoe_tui::{
+    autopilot,
+    board::{game_state, Board, Mark},
+    bots::{Adaptive, Aggressive, Defensive, Difficulty, Mirror, Perfect, Random, Trickster},
+    engine::Engine,
+    learning::Learned,
+    mcts::Mcts,
+    opponent::{Opponent, Seat},
+    protocol::{Connection, Reply, Request},
+    solver::{Solver, Value},
+};
+
+use crate::{
+    chat::{Chat, QUICK},
+    config::Config,
+    editor::{Edit, Editor},
+    history::{History, MODES},
+    lobby::{Lobby, LobbyAction},
+};
+
+type NewBot = fn(&Session) -> Result<Box<dyn Opponent>, String>;
+
+// Start menu entries after the library's own, as (mode, name of player 2, bot playing it)
+pub const BOTS: [(&str, &str, NewBot); 7] = [
+    ("adaptive", "Adaptive computer", |session| {
+        Ok(Box::new(Adaptive::new(session.difficulty.clone())))
+    }),
+    ("aggressive", "Aggressive computer", |_| {
+        Ok(Box::new(Aggressive::new()))
+    }),
+    ("defensive", "Defensive computer", |_| {
+        Ok(Box::new(Defensive::new()))
+    }),
+    ("trickster", "Trickster computer", |_| {
+        Ok(Box::new(Trickster::new()))
+    }),
+    ("mirror", "Mirror computer", |_| Ok(Box::new(Mirror::new()))),
+    ("learned", "Learned computer", |session| {
+        Ok(Box::new(Learned::load(&session.config.table)?))
+    }),
+    ("mcts", "Monte Carlo computer", |session| {
+        Ok(Box::new(Mcts::new(session.config.playouts)))
+    }),
+];
+
+// The library's random and smart computers answer inside App::update, which would block
+// the interface while they think, so their start menu entries seat these instead
+const LIBRARY_COMPUTERS: [NewBot; 2] = [
+    |_| Ok(Box::new(Random::new())),
+    |_| Ok(Box::new(Perfect::new())),
+];
+
+// Screens drawn on top of whatever state the app is in
+pub enum Overlay {
+    Help(u16),
+    // the key is only passed on to the app once the question is answered with y
+    Confirm(String, Key),
+}
+
+// A game against another client of the lobby server
+pub struct Remote {
+    pub room: u32,
+    pub mark: Mark,
+    pub opponent: String,
+    // the board as the server has it
+    synced: Board,
+    // the opponent's moves waiting for the game menu to close
+    pending: Vec<(usize, usize)>,
+}
+
+// A game in a room of the lobby server followed without playing in it
+pub struct Watch {
+    pub room: u32,
+    // the names in the seats of X and O, empty while a seat is free
+    pub players: [String; 2],
+    pub to_move: Mark,
+    pub over: bool,
+    // how long each side had thought when the clocks were last updated
+    clocks: [Duration; 2],
+    since: Instant,
+    // the moves waiting for the game menu to close
+    pending: Vec<(usize, usize)>,
+}
+
+impl Watch {
+    fn new(room: u32) -> Watch {
+        Watch {
+            room,
+            players: Default::default(),
+            to_move: Mark::X,
+            over: false,
+            clocks: [Duration::ZERO; 2],
+            since: Instant::now(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn seat(mark: Mark) -> usize {
+        match mark {
+            Mark::X => 0,
+            Mark::O => 1,
+        }
+    }
+
+    // How long the side has thought in this game, counting the move it is thinking about
+    pub fn clock(&self, mark: Mark) -> Duration {
+        let thinking =
+            !self.over && mark == self.to_move && self.players.iter().all(|name| !name.is_empty());
+        let clock = self.clocks[Watch::seat(mark)];
+        if thinking {
+            clock + self.since.elapsed()
+        } else {
+            clock
+        }
+    }
+
+    fn moved(&mut self) {
+        let seat = Watch::seat(self.to_move);
+        self.clocks[seat] = self.clock(self.to_move);
+        self.since = Instant::now();
+        self.to_move = self.to_move.other();
+    }
+}
+
+// Wraps the library App with the state only the TUI cares about
+pub struct Session {
+    pub app: App,
+    pub overlay: Option<Overlay>,
+    pub editor: Option<Editor>,
+    pub history: History,
+    // shown in the info panel until the next key press
+    pub status: Option<String>,
+    // the value of every move for the side to move, while analysis is turned on
+    pub hints: Vec<((usize, usize), Value)>,
+    // the opponent playing player 2 in place of the built-in computers
+    pub seat: Option<Seat>,
+    // the entry of BOTS selected on the start menu, past the library's entries
+    pub menu: Option<usize>,
+    // shown in place of the start menu while connected to a lobby server
+    pub lobby: Option<Lobby>,
+    pub remote: Option<Remote>,
+    pub watch: Option<Watch>,
+    // messages with the opponents of network games, kept between games
+    pub chat: Chat,
+    connection: Option<Connection>,
+    // the mode of the computer playing in the seat, if it isn't an engine
+    computer: Option<&'static str>,
+    difficulty: Difficulty,
+    // whether the game was over at the last refresh, so each result is only counted once
+    over: bool,
+    analysis: bool,
+    solver: Solver,
+    config: Config,
+}
+
+impl Session {
+    pub fn new(app: App, config: Config) -> Result<Session, String> {
+        let mut session = Session {
+            app,
+            overlay: None,
+            editor: None,
+            history: History::new(),
+            status: None,
+            hints: Vec::new(),
+            seat: None,
+            menu: None,
+            lobby: None,
+            remote: None,
+            watch: None,
+            chat: Chat::new(),
+            connection: None,
+            computer: None,
+            difficulty: Difficulty::new(config.error_rate),
+            over: false,
+            analysis: false,
+            solver: Solver::new(),
+            config,
+        };
+        if let Some(command) = &session.config.engine {
+            let engine = Engine::spawn(command)?;
+            let budget = session.config.engine_time;
+            session.seat = Some(Seat::new(Box::new(engine), Mark::O, budget));
+            session.set_opponent(0);
+        }
+        if let Some(address) = &session.config.connect {
+            let mut connection = Connection::open(address)?;
+            connection.send(&Request::Name(session.config.name.clone()))?;
+            connection.send(&Request::List)?;
+            session.connection = Some(connection);
+            session.lobby = Some(Lobby::new(None));
+        }
+        let Session {
+            app,
+            history,
+            config,
+            ..
+        } = &mut session;
+        if let Some(game) = config.game.take() {
+            autopilot::replay(app, &game, |app| history.observe(app))?;
+        } else if let Some(position) = &config.position {
+            autopilot::set_up(app, position)?;
+        }
+        history.observe(app);
+        Ok(session)
+    }
+
+    pub fn update(&mut self, key: Key) {
+        match (&mut self.overlay, key) {
+            (Some(Overlay::Help(scroll)), Key::Up) => *scroll = scroll.saturating_sub(1),
+            // clamped to the length of the help text when drawn
+            (Some(Overlay::Help(scroll)), Key::Down) => *scroll = scroll.saturating_add(1),
+            (Some(Overlay::Help(_)), Key::Esc | Key::Char('?' | 'q' | 'Q')) => self.overlay = None,
+            (Some(Overlay::Help(_)), _) => {}
+            (Some(Overlay::Confirm(..)), Key::Char('y' | 'Y')) => {
+                if let Some(Overlay::Confirm(_, key)) = self.overlay.take() {
+                    if self.leaves_room(&key) {
+                        self.leave_room();
+                    } else {
+                        self.app.update(key);
+                    }
+                }
+            }
+            (Some(Overlay::Confirm(..)), _) => self.overlay = None,
+            (None, Key::Char('?')) if self.chat.input.is_none() => {
+                self.overlay = Some(Overlay::Help(0))
+            }
+            (None, key) => self.handle(key),
+        }
+        self.refresh();
+    }
+
+    // Called on every tick to let the opponent move
+    pub fn tick(&mut self) {
+        if let Some(seat) = &mut self.seat {
+            if seat.poll(&mut self.app) {
+                self.refresh();
+            }
+        }
+        if self.connection.is_some() {
+            self.receive();
+            self.refresh();
+        }
+    }
+
+    fn send(&mut self, request: Request) {
+        let sent = match &mut self.connection {
+            Some(connection) => connection.send(&request),
+            None => return,
+        };
+        if let Err(error) = sent {
+            self.disconnect(error);
+        }
+    }
+
+    fn disconnect(&mut self, error: String) {
+        self.connection = None;
+        self.remote = None;
+        self.watch = None;
+        self.lobby = None;
+        self.app.warning_message = Some(error.clone());
+        self.status = Some(error);
+    }
+
+    fn receive(&mut self) {
+        let replies = match self.connection.as_ref().map(Connection::poll) {
+            Some(Ok(replies)) => replies,
+            Some(Err(error)) => return self.disconnect(error),
+            None => return,
+        };
+        for reply in replies {
+            match reply {
+                Reply::Start {
+                    room,
+                    mark,
+                    opponent,
+                } => {
+                    self.new_game();
+                    let name = self.config.name.as_str();
+                    let players = match mark {
+                        Mark::X => [name, &opponent],
+                        Mark::O => [&opponent, name],
+                    };
+                    self.history.set_players("network", players);
+                    self.chat.push(
+                        None,
+                        format!("New game against {}, you play {}", opponent, mark),
+                    );
+                    self.remote = Some(Remote {
+                        room,
+                        mark,
+                        opponent,
+                        synced: Board::default(),
+                        pending: Vec::new(),
+                    });
+                }
+                Reply::Moved(cell) => {
+                    if let Some(remote) = &mut self.remote {
+                        remote.pending.push(cell);
+                    }
+                    if let Some(watch) = &mut self.watch {
+                        watch.pending.push(cell);
+                        watch.moved();
+                    }
+                }
+                Reply::Said(text) => {
+                    if let Some(remote) = &self.remote {
+                        self.chat.push(Some(remote.opponent.clone()), text);
+                    }
+                }
+                Reply::Left => {
+                    if let Some(remote) = self.remote.take() {
+                        self.chat
+                            .push(None, format!("{} left the game", remote.opponent));
+                        self.chat.input = None;
+                        // still in the room, waiting for someone else to join
+                        let mut lobby = Lobby::new(Some(remote.room));
+                        lobby.message = Some(format!("{} left the game", remote.opponent));
+                        self.lobby = Some(lobby);
+                        self.send(Request::List);
+                    }
+                }
+                Reply::Watching(room) => {
+                    self.new_game();
+                    self.watch = Some(Watch::new(room));
+                }
+                Reply::Seat { mark, clock, name } => {
+                    if let Some(watch) = &mut self.watch {
+                        let seat = Watch::seat(mark);
+                        watch.players[seat] = name;
+                        watch.clocks[seat] = clock;
+                        watch.since = Instant::now();
+                        let [x, o] = &watch.players;
+                        self.history.set_players("network", [x, o]);
+                    }
+                }
+                Reply::Closed => {
+                    if let Some(watch) = self.watch.take() {
+                        let mut lobby = Lobby::new(None);
+                        lobby.message = Some(format!("Room {} closed", watch.room));
+                        self.lobby = Some(lobby);
+                        self.send(Request::List);
+                    }
+                }
+                // the library ends the game on its own
+                Reply::Over(_) => {
+                    if let Some(watch) = &mut self.watch {
+                        let seat = Watch::seat(watch.to_move);
+                        watch.clocks[seat] = watch.clock(watch.to_move);
+                        watch.over = true;
+                    }
+                }
+                Reply::Error(error) if self.lobby.is_none() => {
+                    self.app.warning_message = Some(error)
+                }
+                reply => {
+                    if let Some(lobby) = &mut self.lobby {
+                        lobby.receive(reply);
+                    }
+                }
+            }
+        }
+        self.play_remote_moves();
+    }
+
+    // Starts the board afresh for a game from the server
+    fn new_game(&mut self) {
+        if let Err(error) = autopilot::new_game(&mut self.app) {
+            self.app.warning_message = Some(error);
+        }
+        self.lobby = None;
+        // so the first move isn't taken for a new game starting from it
+        self.history.observe(&self.app);
+    }
+
+    fn play_remote_moves(&mut self) {
+        if !matches!(self.app.state, AppState::Playing(_)) {
+            return;
+        }
+        let pending = match (&mut self.remote, &mut self.watch) {
+            (Some(remote), _) => std::mem::take(&mut remote.pending),
+            (_, Some(watch)) => std::mem::take(&mut watch.pending),
+            _ => return,
+        };
+        for cell in pending {
+            if let Err(error) = autopilot::place(&mut self.app, cell) {
+                self.app.warning_message = Some(error);
+            }
+            self.history.observe(&self.app);
+        }
+        if let (Some(remote), Some(board)) = (&mut self.remote, Board::of(&self.app)) {
+            remote.synced = board;
+        }
+    }
+
+    // Sends the local player's move to the server once it is on the board
+    fn send_move(&mut self) {
+        let (remote, board) = match (&mut self.remote, Board::of(&self.app)) {
+            (Some(remote), Some(board)) => (remote, board),
+            _ => return,
+        };
+        let played: Vec<_> = board
+            .cells()
+            .filter(|&(cell, mark)| mark.is_some() && remote.synced.get(cell).is_none())
+            .collect();
+        if let [(cell, Some(mark))] = played.as_slice() {
+            if *mark == remote.mark {
+                remote.synced = board;
+                let cell = *cell;
+                self.send(Request::Move(cell));
+            }
+        }
+    }
+
+    fn lobby_action(&mut self, action: LobbyAction) {
+        match action {
+            LobbyAction::Continue => {}
+            LobbyAction::Refresh => self.send(Request::List),
+            LobbyAction::Create => self.send(Request::Create),
+            LobbyAction::Join(room) => self.send(Request::Join(room)),
+            LobbyAction::Watch(room) => self.send(Request::Watch(room)),
+            LobbyAction::Leave => {
+                self.send(Request::Leave);
+                self.lobby = Some(Lobby::new(None));
+                self.send(Request::List);
+            }
+            LobbyAction::Quit => self.app.state = AppState::Quit,
+        }
+    }
+
+    // Leaves the room of a network game, back to the lobby
+    fn leave_room(&mut self) {
+        self.remote = None;
+        self.chat.input = None;
+        self.send(Request::Leave);
+        self.lobby = Some(Lobby::new(None));
+        self.send(Request::List);
+    }
+
+    // Stops watching a game, back to the lobby
+    fn stop_watching(&mut self) {
+        self.watch = None;
+        self.send(Request::Leave);
+        self.lobby = Some(Lobby::new(None));
+        self.send(Request::List);
+    }
+
+    // Types and sends chat messages during a network game, returning whether the key
+    // was used
+    fn handle_chat(&mut self, key: &Key) -> bool {
+        let remote = match &self.remote {
+            Some(remote) => remote,
+            None => return false,
+        };
+        // the arrow keys only move the cursor on our turn
+        let waiting = match &self.app.state {
+            AppState::Playing(GameState::GameInProgress(_, player, _)) => {
+                Mark::of(*player) != remote.mark
+            }
+            AppState::Playing(GameState::GameOver(..)) => true,
+            _ => false,
+        };
+        let text = match key {
+            _ if self.chat.input.is_some() => self.chat.update(key),
+            Key::Up if waiting => {
+                self.chat.scroll_back();
+                None
+            }
+            Key::Down if waiting => {
+                self.chat.scroll_forward();
+                None
+            }
+            Key::Char('/' | '\t') => {
+                self.chat.input = Some(String::new());
+                None
+            }
+            Key::Char(c @ '1'..='4') => Some(QUICK[*c as usize - '1' as usize].to_string()),
+            _ => return false,
+        };
+        if let Some(text) = text {
+            self.chat.push(Some(self.config.name.clone()), text.clone());
+            self.send(Request::Say(text));
+        }
+        true
+    }
+
+    // Handles the keys that work differently in a network game, returning whether the
+    // key was used
+    fn handle_remote(&mut self, key: &Key) -> bool {
+        let remote = match &self.remote {
+            Some(remote) => remote,
+            None => return false,
+        };
+        match (&self.app.state, key) {
+            _ if self.leaves_room(key) && self.config.confirm => {
+                let question = match game_state(&self.app) {
+                    Some(GameState::GameInProgress(..)) => "Abandon the game and leave the room?",
+                    _ => "Leave the room and go back to the lobby?",
+                };
+                let key = match self.app.state {
+                    AppState::GameMenu(_) => Key::Enter,
+                    _ => Key::Char('l'),
+                };
+                self.overlay = Some(Overlay::Confirm(question.to_string(), key));
+            }
+            _ if self.leaves_room(key) => self.leave_room(),
+            (AppState::Playing(GameState::GameOver(..)), Key::Char('r' | 'R')) => {
+                let waiting = format!("Waiting for {} to play again", remote.opponent);
+                self.send(Request::Again);
+                self.app.warning_message = Some(waiting);
+            }
+            (AppState::Playing(_), Key::Char('r' | 'R')) => {
+                self.app.warning_message =
+                    Some("A network game can't be reset, press L to leave it".to_string())
+            }
+            (AppState::Playing(GameState::GameInProgress(_, player, _)), key)
+                if Mark::of(*player) != remote.mark && is_move_key(key) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    // Whether the key takes the player of a network game back to the lobby
+    fn leaves_room(&self, key: &Key) -> bool {
+        self.remote.is_some()
+            && matches!(
+                (&self.app.state, key),
+                (AppState::Playing(_), Key::Char('l' | 'L')) | (AppState::GameMenu(1), Key::Enter)
+            )
+    }
+
+    // Handles the keys while watching a game, which only leave it, quit or open the game
+    // menu, returning whether the key was used
+    fn handle_watch(&mut self, key: &Key) -> bool {
+        if self.watch.is_none() {
+            return false;
+        }
+        match (&self.app.state, key) {
+            (AppState::Playing(_), Key::Char('l' | 'L')) | (AppState::GameMenu(1), Key::Enter) => {
+                self.stop_watching()
+            }
+            (AppState::Playing(_), Key::Char('r' | 'R')) => {
+                self.app.warning_message =
+                    Some("You are only watching, press L to leave".to_string())
+            }
+            // there is no game of ours to abandon
+            (AppState::Playing(_), Key::Char('q' | 'Q')) => self.app.update(Key::Char('q')),
+            (AppState::Playing(_), key) if is_move_key(key) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    // The external engine given on the command line, which stays seated for every game
+    pub fn engine(&self) -> Option<&Seat> {
+        self.config.engine.as_ref().and(self.seat.as_ref())
+    }
+
+    // Whether the game on the board is played or watched on the lobby server, where the
+    // solver's hints would give the game away
+    pub fn networked(&self) -> bool {
+        self.remote.is_some() || self.watch.is_some()
+    }
+
+    // The adaptive computer's difficulty while it is playing
+    pub fn adaptive(&self) -> Option<&Difficulty> {
+        match self.computer {
+            Some("adaptive") => Some(&self.difficulty),
+            _ => None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.send_move();
+        self.history.observe(&self.app);
+        self.record_result();
+        self.analyze();
+    }
+
+    // Tells the adaptive computer how the human playing player 1 did once a game ends
+    fn record_result(&mut self) {
+        let winner = match game_state(&self.app) {
+            Some(GameState::GameOver(winner, _)) => Some(*winner),
+            _ => None,
+        };
+        if let (Some(winner), false, Some(difficulty)) = (winner, self.over, self.adaptive()) {
+            difficulty.record(match winner {
+                Some(Player::Player1) => 1.0,
+                Some(Player::Player2) => 0.0,
+                None => 0.5,
+            });
+        }
+        self.over = winner.is_some();
+    }
+
+    // Moves through the bots listed after the library's entries, which the library
+    // doesn't know about, returning whether the key was used
+    fn navigate_menu(&mut self, row: u8, key: &Key) -> bool {
+        match (self.menu, key) {
+            (None, Key::Down) if row as usize == MODES.len() - 1 => self.menu = Some(0),
+            (Some(0), Key::Up) => self.menu = None,
+            (Some(bot), Key::Up) => self.menu = Some(bot - 1),
+            (Some(bot), Key::Down) => self.menu = Some((bot + 1).min(BOTS.len() - 1)),
+            _ => return false,
+        }
+        true
+    }
+
+    // The computer highlighted on the start menu, as an entry like those of BOTS
+    fn highlighted(&self) -> Option<(&'static str, &'static str, NewBot)> {
+        match (self.menu, &self.app.state) {
+            _ if self.config.engine.is_some() => None,
+            (Some(bot), _) => Some(BOTS[bot]),
+            (None, AppState::StartMenu(row)) if *row > 0 => {
+                let row = (*row as usize).min(MODES.len() - 1);
+                Some((MODES[row].0, MODES[row].1, LIBRARY_COMPUTERS[row - 1]))
+            }
+            _ => None,
+        }
+    }
+
+    // Computers take player 2 in a game between two humans, like engines, starting from
+    // the position set up in the editor if there is one
+    fn start_computer(
+        &mut self,
+        (mode, name, new_bot): (&'static str, &str, NewBot),
+        position: Option<&Board>,
+    ) -> Result<(), String> {
+        let opponent = new_bot(self)?;
+        match position {
+            Some(position) => autopilot::set_up(&mut self.app, position)?,
+            None => autopilot::start_game(&mut self.app)?,
+        }
+        let budget = self.config.engine_time;
+        self.seat = Some(Seat::new(opponent, Mark::O, budget).named(name));
+        self.computer = Some(mode);
+        self.menu = None;
+        self.history.set_opponent(mode, name);
+        Ok(())
+    }
+
+    // Plays the position against whoever is highlighted on the start menu
+    fn start_position(&mut self, position: &Board) -> Result<(), String> {
+        if let Some(computer) = self.highlighted() {
+            return self.start_computer(computer, Some(position));
+        }
+        autopilot::set_up(&mut self.app, position)?;
+        self.unseat_computer();
+        self.set_opponent(0);
+        Ok(())
+    }
+
+    fn unseat_computer(&mut self) {
+        if self.computer.take().is_some() {
+            self.seat = None;
+        }
+    }
+
+    fn set_opponent(&mut self, row: usize) {
+        match &self.seat {
+            Some(seat) => self.history.set_opponent("engine", seat.name()),
+            None => self.history.set_mode(row),
+        }
+    }
+
+    fn analyze(&mut self) {
+        self.hints = match Board::of(&self.app) {
+            Some(board) if self.analysis && !self.networked() => self.solver.moves(&board),
+            _ => Vec::new(),
+        };
+    }
+
+    fn handle(&mut self, key: Key) {
+        self.status = None;
+        if let Some(editor) = &mut self.editor {
+            match editor.update(key) {
+                Edit::Continue => {}
+                Edit::Cancel => self.editor = None,
+                Edit::Start(position) => match self.start_position(&position) {
+                    Ok(()) => self.editor = None,
+                    Err(error) => {
+                        if let Some(editor) = &mut self.editor {
+                            editor.error = Some(error);
+                        }
+                    }
+                },
+            }
+            return;
+        }
+        if let Some(lobby) = &mut self.lobby {
+            let action = lobby.update(key);
+            return self.lobby_action(action);
+        }
+        if self.handle_chat(&key) || self.handle_remote(&key) || self.handle_watch(&key) {
+            return;
+        }
+        if let AppState::StartMenu(row) = self.app.state {
+            if self.config.engine.is_none() && self.navigate_menu(row, &key) {
+                return;
+            }
+        }
+        match (&self.app.state, &key) {
+            (AppState::StartMenu(_), Key::Char('e' | 'E')) => {
+                self.editor = Some(Editor::new());
+                return;
+            }
+            (AppState::StartMenu(_), Key::Enter) if self.config.engine.is_some() => {
+                // the engine takes player 2 in a game between two humans
+                if let Err(error) = autopilot::start_game(&mut self.app) {
+                    self.app.warning_message = Some(error);
+                }
+                self.set_opponent(0);
+                return;
+            }
+            (AppState::StartMenu(_), Key::Enter) => match self.highlighted() {
+                Some(computer) => {
+                    if let Err(error) = self.start_computer(computer, None) {
+                        // stays on the start menu to pick something else
+                        self.status = Some(error);
+                    }
+                    return;
+                }
+                None => {
+                    self.unseat_computer();
+                    self.set_opponent(0);
+                }
+            },
+            (AppState::GameMenu(_), Key::Char('e' | 'E')) => {
+                self.export();
+                return;
+            }
+            (AppState::GameMenu(_), Key::Char('a' | 'A')) if self.networked() => {
+                self.status = Some("Analysis is off in network games".to_string());
+                return;
+            }
+            (AppState::GameMenu(_), Key::Char('a' | 'A')) => {
+                self.analysis = !self.analysis;
+                self.status = Some(format!(
+                    "Analysis turned {}",
+                    if self.analysis { "on" } else { "off" }
+                ));
+                return;
+            }
+            _ => {}
+        }
+        let opponent_to_play = match &self.seat {
+            Some(seat) => seat.to_play(&self.app),
+            None => false,
+        };
+        if opponent_to_play && is_move_key(&key) {
+            return;
+        }
+        match self.abandons_game(&key) {
+            Some(question) if self.config.confirm => {
+                self.overlay = Some(Overlay::Confirm(question.to_string(), key))
+            }
+            _ => self.app.update(key),
+        }
+    }
+
+    fn export(&mut self) {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let path = format!("tictactoe-{}.txt", secs);
+        let game = self.history.game(&self.app);
+        self.status = Some(match fs::write(&path, game.to_string()) {
+            Ok(()) => format!("Saved the game to {}", path),
+            Err(err) => format!("Could not save the game to {}: {}", path, err),
+        });
+    }
+
+    fn abandons_game(&self, key: &Key) -> Option<&'static str> {
+        match (&self.app.state, key) {
+            (AppState::Playing(GameState::GameInProgress(cells, ..)), Key::Char('q' | 'Q'))
+                if has_moves(cells) =>
+            {
+                Some("Abandon current game and quit?")
+            }
+            (AppState::Playing(GameState::GameInProgress(cells, ..)), Key::Char('r' | 'R'))
+                if has_moves(cells) =>
+            {
+                Some("Abandon current game and reset the board?")
+            }
+            (AppState::GameMenu(1), Key::Enter) => match &self.app.prev_state {
+                Some(GameState::GameInProgress(cells, ..)) if has_moves(cells) => {
+                    Some("Abandon current game and start a new one?")
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn is_move_key(key: &Key) -> bool {
+    matches!(
+        key,
+        Key::Up | Key::Down | Key::Left | Key::Right | Key::Enter | Key::Char('p' | 'P')
+    )
+}
+
+fn has_moves(cells: &Cells) -> bool {
+    cells
+        .iter()
+        .flatten()
+        .any(|cell| !matches!(cell, GameCell::Empty))
+}
@@ -0,0 +1,153 @@
+use std::{collections::HashMap, fmt};
+
+use crate::board::Board;
+
+// The outcome of perfect play for one side, with the number of moves until the game ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    Win(u8),
+    Draw,
+    Loss(u8),
+}
+
+impl Value {
+    // Higher is better: quick wins first and slow losses last
+    fn rank(self) -> i32 {
+        match self {
+            Value::Win(moves) => 100 - moves as i32,
+            Value::Draw => 0,
+            Value::Loss(moves) => moves as i32 - 100,
+        }
+    }
+
+    // The value for the player who made the move leading to a position with this value
+    fn before(self) -> Value {
+        match self {
+            Value::Win(moves) => Value::Loss(moves + 1),
+            Value::Draw => Value::Draw,
+            Value::Loss(moves) => Value::Win(moves + 1),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Win(moves) => write!(f, "W{}", moves),
+            Value::Draw => write!(f, "D"),
+            Value::Loss(moves) => write!(f, "L{}", moves),
+        }
+    }
+}
+
+// Exhaustive search over every position reachable from the ones asked about
+#[derive(Default)]
+pub struct Solver {
+    memo: HashMap<Board, Value>,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            memo: HashMap::new(),
+        }
+    }
+
+    // The value of a position for the side to move
+    pub fn value(&mut self, board: &Board) -> Value {
+        if let Some(value) = self.memo.get(board) {
+            return *value;
+        }
+        let value = if board.winner().is_some() {
+            Value::Loss(0)
+        } else if board.is_full() {
+            Value::Draw
+        } else {
+            self.moves(board)
+                .into_iter()
+                .map(|(_, value)| value)
+                .max_by_key(|value| value.rank())
+                .unwrap_or(Value::Draw)
+        };
+        self.memo.insert(*board, value);
+        value
+    }
+
+    // The moves leading to the best outcome for the side to move
+    pub fn best_moves(&mut self, board: &Board) -> Vec<(usize, usize)> {
+        let moves = self.moves(board);
+        let best = moves.iter().map(|(_, value)| value.rank()).max();
+        moves
+            .into_iter()
+            .filter(|(_, value)| Some(value.rank()) == best)
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+
+    // The value of playing each empty cell, for the side to move
+    pub fn moves(&mut self, board: &Board) -> Vec<((usize, usize), Value)> {
+        if board.winner().is_some() {
+            return Vec::new();
+        }
+        let to_move = board.turn();
+        board
+            .cells()
+            .filter(|(_, mark)| mark.is_none())
+            .map(|(cell, _)| {
+                let mut next = *board;
+                next.set(cell, Some(to_move));
+                (cell, self.value(&next).before())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{parse_cell, parse_position};
+
+    fn value_of(solver: &mut Solver, board: &Board, cell: &str) -> Value {
+        let cell = parse_cell(cell).unwrap();
+        let (_, value) = solver
+            .moves(board)
+            .into_iter()
+            .find(|(c, _)| *c == cell)
+            .unwrap();
+        value
+    }
+
+    #[test]
+    fn empty_board_is_a_draw() {
+        let mut solver = Solver::new();
+        let board = Board::default();
+        assert_eq!(solver.value(&board), Value::Draw);
+        let moves = solver.moves(&board);
+        assert_eq!(moves.len(), 9);
+        assert!(moves.iter().all(|(_, value)| *value == Value::Draw));
+    }
+
+    #[test]
+    fn immediate_win_is_one_move() {
+        let mut solver = Solver::new();
+        let board = parse_position("xx./oo./...").unwrap();
+        assert_eq!(value_of(&mut solver, &board, "c1"), Value::Win(1));
+        assert_eq!(solver.value(&board), Value::Win(1));
+        assert_eq!(solver.best_moves(&board), vec![parse_cell("c1").unwrap()]);
+        assert_eq!(Value::Win(1).to_string(), "W1");
+    }
+
+    #[test]
+    fn forced_loss_counts_the_moves() {
+        let mut solver = Solver::new();
+        // X threatens b1 and c2, so O can only block one of them
+        let board = parse_position("x.x/.o./o.x o").unwrap();
+        assert_eq!(solver.value(&board), Value::Loss(2));
+        assert_eq!(value_of(&mut solver, &board, "b1"), Value::Loss(2));
+        assert_eq!(value_of(&mut solver, &board, "a2"), Value::Loss(2));
+        // after blocking one threat X wins with the other
+        let blocked = parse_position("xox/.o./o.x x").unwrap();
+        assert_eq!(value_of(&mut solver, &blocked, "c2"), Value::Win(1));
+        assert_eq!(solver.value(&blocked), Value::Win(1));
+    }
+}
@@ -0,0 +1,173 @@
+// The train subcommand, which learns a table by self-play and prints how it does
+// against a random computer and a stronger one as it goes
+use std::{process, thread, time::Duration};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use tictactoe_tui::{
+    board::Mark,
+    bots::Perfect,
+    learning::{self, Grid, Table},
+    mcts,
+    opponent::Opponent,
+};
+
+const USAGE: &str = "\
+usage: tic-tac-toe train [options]
+
+Prints a CSV with the learned computer's share of wins, draws and losses every so
+many games, against a random computer and, on 3x3, the perfect one or, on 4x4, Monte
+Carlo tree search with 200 playouts a move.
+
+options:
+    --size <n>          board size, 3 or 4 (default 3)
+    --episodes <n>      games to play against itself (default 50000 on 3x3,
+                        200000 on 4x4)
+    --every <n>         games between measurements (default a twentieth)
+    --games <n>         games against each computer per measurement (default 200)
+    --alpha <rate>      learning rate (default 0.2)
+    --epsilon <rate>    share of random moves while learning (default 0.1)
+    --out <file>        where to save the table (default learned-<n>x<n>.txt)";
+
+struct Options {
+    size: usize,
+    episodes: Option<usize>,
+    every: Option<usize>,
+    games: usize,
+    alpha: f64,
+    epsilon: f64,
+    out: Option<String>,
+}
+
+fn options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        size: 3,
+        episodes: None,
+        every: None,
+        games: 200,
+        alpha: 0.2,
+        epsilon: 0.1,
+        out: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--size" => options.size = parse(&arg, &value()?)?,
+            "--episodes" => options.episodes = Some(parse(&arg, &value()?)?),
+            "--every" => options.every = Some(parse(&arg, &value()?)?),
+            "--games" => options.games = parse(&arg, &value()?)?,
+            "--alpha" => options.alpha = parse(&arg, &value()?)?,
+            "--epsilon" => options.epsilon = parse(&arg, &value()?)?,
+            "--out" => options.out = Some(value()?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    if !(3..=4).contains(&options.size) {
+        return Err("--size needs to be 3 or 4".to_string());
+    }
+    if !(0.0..=1.0).contains(&options.alpha) || !(0.0..=1.0).contains(&options.epsilon) {
+        return Err("--alpha and --epsilon need to be between 0 and 1".to_string());
+    }
+    Ok(options)
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} got {:?}, which isn't a number", flag, value))
+}
+
+type Move = Box<dyn FnMut(&Grid, &mut StdRng) -> Option<usize>>;
+
+// The computers the table is measured against, by the name used in the CSV header
+fn computers(size: usize) -> [(&'static str, Move); 2] {
+    let stronger: (&str, Move) = match size {
+        3 => {
+            // a table that has learned the game draws every game against it
+            let mut perfect = Perfect::new();
+            let choose = move |grid: &Grid, _: &mut StdRng| {
+                let (row, col) = perfect.choose(&grid.to_board()?, Duration::ZERO).ok()?;
+                Some(row * 3 + col)
+            };
+            ("perfect", Box::new(choose))
+        }
+        // the solver can't search bigger boards, but a few playouts a move are enough for
+        // Monte Carlo tree search to never lose to a computer that just blocks
+        _ => {
+            let cores = thread::available_parallelism().map_or(4, |n| n.get());
+            let choose = move |grid: &Grid, _: &mut StdRng| {
+                mcts::best_move(&mcts::search(grid, 200, Duration::MAX, cores))
+            };
+            ("mcts", Box::new(choose))
+        }
+    };
+    [("random", Box::new(learning::random_move)), stronger]
+}
+
+// Plays the table without exploring against a computer, alternating who starts, and
+// returns the table's share of wins, draws and losses
+fn evaluate(table: &Table, computer: &mut Move, games: usize, rng: &mut StdRng) -> [f64; 3] {
+    let mut results = [0; 3];
+    for game in 0..games {
+        let learned = if game % 2 == 0 { Mark::X } else { Mark::O };
+        let mut grid = Grid::new(table.size);
+        while !grid.is_over() {
+            let cell = if grid.turn() == learned {
+                table.best_move(&grid, rng)
+            } else {
+                computer(&grid, rng)
+            };
+            grid = match cell {
+                Some(cell) => grid.play(cell),
+                None => break,
+            };
+        }
+        match grid.winner() {
+            Some(winner) if winner == learned => results[0] += 1,
+            None => results[1] += 1,
+            Some(_) => results[2] += 1,
+        }
+    }
+    results.map(|count| count as f64 / games.max(1) as f64)
+}
+
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let options = options(args)?;
+    let episodes = options
+        .episodes
+        .unwrap_or(if options.size == 3 { 50_000 } else { 200_000 });
+    let every = options.every.unwrap_or(episodes / 20).max(1);
+    let out = options
+        .out
+        .unwrap_or_else(|| learning::default_path(options.size));
+
+    let mut rng = StdRng::from_entropy();
+    let mut table = Table::new(options.size);
+    let mut computers = computers(options.size);
+    let mut header = vec!["episodes".to_string()];
+    for (name, _) in &computers {
+        header.extend(["wins", "draws", "losses"].map(|result| format!("{}_{}", name, result)));
+    }
+    println!("{}", header.join(","));
+    for episode in 0..=episodes {
+        if episode % every == 0 || episode == episodes {
+            let mut row = vec![episode.to_string()];
+            for (_, computer) in &mut computers {
+                let results = evaluate(&table, computer, options.games, &mut rng);
+                row.extend(results.map(|share| format!("{:.3}", share)));
+            }
+            println!("{}", row.join(","));
+            eprintln!("{}/{} games, {} positions", episode, episodes, table.len());
+        }
+        if episode < episodes {
+            table.self_play(&mut rng, options.alpha, options.epsilon);
+        }
+    }
+    table.save(&out)?;
+    eprintln!("Saved the table to {}", out);
+    Ok(())
+}
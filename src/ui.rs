@@ -1,17 +1,33 @@
+use std::time::Duration;
+
 use tictactoe_library::{
-    app::{App, AppState},
-    game::{Cells, GameState, Player, Position, GameCell},
+    app::AppState,
+    game::{Cells, GameState, Player},
 };
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
 
-pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+use tictactoe_tui::{
+    board::{game_cell, Board, Mark},
+    opponent::Seat,
+    solver::Value,
+};
+
+use crate::{
+    chat::Chat,
+    editor::Editor,
+    history::MODES,
+    lobby::Lobby,
+    session::{Overlay, Session, Watch, BOTS},
+};
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, session: &mut Session) {
     let rects = Layout::default()
         .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
         .split(f.size());
@@ -19,50 +35,113 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .direction(Direction::Horizontal)
         .split(rects[0]);
-    let state = &app.state;
-    match state {
+    if let Some(editor) = &session.editor {
+        draw_editor(f, editor, &main, &rects[1]);
+    } else if let Some(lobby) = &session.lobby {
+        draw_lobby(f, lobby, &main, &rects[1]);
+    } else {
+        draw_app(f, session, &main, &rects[1]);
+    }
+    let bindings = help_bindings(session);
+    match &mut session.overlay {
+        Some(Overlay::Help(scroll)) => draw_help(f, bindings, scroll),
+        Some(Overlay::Confirm(question, _)) => draw_confirm(f, question),
+        None => {}
+    }
+}
+
+fn draw_app<B: Backend>(f: &mut Frame<B>, session: &Session, main: &[Rect], info: &Rect) {
+    let app = &session.app;
+    match &app.state {
         AppState::StartMenu(row) => {
-            draw_start_menu(f, *row as usize);
+            let row = session.menu.map_or(*row as usize, |bot| MODES.len() + bot);
+            draw_start_menu(f, start_menu_entries(session), row);
+            if let Some(status) = &session.status {
+                draw_status(f, info, status);
+            }
         }
         AppState::Playing(game_state) => {
-            match game_state {
-                GameState::GameInProgress(cells, _, pos) => {
-                    draw_board(f, cells.to_vec(), pos.clone(), &main[0]);
-                }
-                GameState::GameOver(winner, cells) => {
-                    draw_game_over(f, &main[0], *winner, cells.clone());
-                }
-            }
+            // watchers have nothing to place, so they get no cursor
+            let cursor = session.watch.is_none();
+            draw_game(
+                f,
+                game_state,
+                &session.hints,
+                &main[0],
+                Style::default(),
+                cursor,
+            );
             match &app.warning_message {
-                Some(message) => draw_warning(f, &rects[1], message.to_string()),
-                None => draw_info(f, &rects[1], game_state),
+                _ if session.remote.is_some() => {
+                    draw_chat(f, info, &session.chat, app.warning_message.as_deref())
+                }
+                Some(message) => draw_warning(f, info, message.to_string()),
+                None if session.watch.is_some() => draw_watch_info(f, info),
+                None => draw_info(f, info, game_state, session.seat.as_ref()),
             }
 
-            draw_score(f, app, &main[1], game_state);
+            draw_score(f, session, &main[1], game_state);
         }
         AppState::GameMenu(row) => {
-            let mut menu = Layout::default()
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                .direction(Direction::Vertical)
-                .split(main[0]);
-            draw_game_menu(f, &mut menu[0], *row);
-            // draw_score(f, app, &main[1], &app.prev_state.unwrap());
+            // keep the paused game visible behind the menu
+            if let Some(game_state) = &app.prev_state {
+                let dimmed = Style::default().add_modifier(Modifier::DIM);
+                draw_game(
+                    f,
+                    game_state,
+                    &session.hints,
+                    &main[0],
+                    dimmed,
+                    session.watch.is_none(),
+                );
+                draw_score(f, session, &main[1], game_state);
+            }
+            draw_game_menu(f, &centered_rect(60, 50, main[0]), *row);
+            match &session.status {
+                Some(status) => draw_status(f, info, status),
+                None if session.remote.is_some() => draw_chat(f, info, &session.chat, None),
+                None => {}
+            }
         }
         _ => {}
     }
 }
 
-fn draw_start_menu<B: Backend>(f: &mut Frame<B>, row: usize) {
+fn start_menu_entries(session: &Session) -> Vec<String> {
+    // a bot's seat outlives its game, but the menu still lists every opponent
+    match session.engine() {
+        Some(seat) => vec![format!("Play against {}", seat.name())],
+        None => {
+            let mut entries = vec![
+                "Play against human".to_string(),
+                "Play against random computer".to_string(),
+                "Play against smart computer".to_string(),
+            ];
+            entries.extend(
+                BOTS.iter()
+                    .map(|(_, name, _)| format!("Play against {}", name.to_lowercase())),
+            );
+            entries
+        }
+    }
+}
+
+fn draw_start_menu<B: Backend>(f: &mut Frame<B>, entries: Vec<String>, row: usize) {
     let rect = Layout::default()
         .constraints([Constraint::Percentage(100)].as_ref())
         .direction(Direction::Vertical)
         .split(f.size());
-    let menu = Table::new([
-        Row::new([Cell::from("Play against human")]),
-        Row::new([Cell::from("Play against random computer")]),
-        Row::new([Cell::from("Play against smart computer")]),
-    ])
-    .block(Block::default().borders(Borders::ALL).title("Start Menu"))
+    let row = row.min(entries.len() - 1);
+    let menu = Table::new(
+        entries
+            .into_iter()
+            .map(|entry| Row::new([Cell::from(entry)])),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Start Menu (press E to set up a position)"),
+    )
     .highlight_style(Style::default().fg(Color::Yellow))
     .highlight_symbol(">>")
     .widths([Constraint::Percentage(100)].as_ref());
@@ -79,16 +158,41 @@ fn draw_game_menu<B: Backend>(f: &mut Frame<B>, rect: &Rect, row: u8) {
         Row::new([Cell::from("New Game")]),
         Row::new([Cell::from("Quit")]),
     ])
-    .block(Block::default().borders(Borders::ALL).title("Menu"))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Menu (E: export the game, A: analyze)"),
+    )
     .widths(&[Constraint::Percentage(100)])
     .highlight_style(selected_style)
     .highlight_symbol(">>");
 
     let mut state = TableState::default();
     state.select(Some(row.into()));
+    f.render_widget(Clear, *rect);
     f.render_stateful_widget(table, *rect, &mut state)
 }
 
+fn draw_game<B: Backend>(
+    f: &mut Frame<B>,
+    game_state: &GameState,
+    hints: &[((usize, usize), Value)],
+    rect: &Rect,
+    style: Style,
+    cursor: bool,
+) {
+    match game_state {
+        GameState::GameInProgress(cells, _, pos) => {
+            let board = Board::from_cells(cells);
+            let cursor = cursor.then(|| pos.to_tuple());
+            draw_board(f, &board, cursor, hints, rect, style);
+        }
+        GameState::GameOver(winner, cells) => {
+            draw_game_over(f, rect, *winner, cells.clone(), style);
+        }
+    }
+}
+
 fn get_color(player: Player) -> Color {
     match player {
         Player::Player1 => Color::Red,
@@ -96,30 +200,145 @@ fn get_color(player: Player) -> Color {
     }
 }
 
-fn draw_score<B: Backend>(f: &mut Frame<B>, app: &App, rect: &Rect, game_state: &GameState) {
-    let table = Table::new(vec![
-        Row::new(vec![Cell::from("Score:".to_string())]),
-        Row::new(vec![Cell::from(format!(
-            "Player 1's score: {}\nPlayer 2's score: {}",
-            app.score.player1, app.score.player2
-        ))])
-        .style(Style::default().fg(Color::Yellow))
-        .height(2),
-        if let GameState::GameInProgress(_, player, _) = game_state {
+fn get_mark_color(mark: Option<Mark>) -> Color {
+    match mark {
+        Some(mark) => get_color(mark.player()),
+        None => Color::Gray,
+    }
+}
+
+fn get_value_color(value: Value) -> Color {
+    match value {
+        Value::Win(_) => Color::Green,
+        Value::Draw => Color::Yellow,
+        Value::Loss(_) => Color::Magenta,
+    }
+}
+
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+fn draw_score<B: Backend>(
+    f: &mut Frame<B>,
+    session: &Session,
+    rect: &Rect,
+    game_state: &GameState,
+) {
+    if let Some(watch) = &session.watch {
+        return draw_watch(f, session, watch, rect);
+    }
+    let app = &session.app;
+    let thinking = session
+        .seat
+        .as_ref()
+        .and_then(|seat| Some((seat, seat.thinking()?)));
+    let mut rows = Vec::new();
+    // the library keeps the score by player, but network players swap marks every game
+    if !session.networked() {
+        rows.push(Row::new(vec![Cell::from("Score:".to_string())]));
+        rows.push(
+            Row::new(vec![Cell::from(format!(
+                "Player 1's score: {}\nPlayer 2's score: {}",
+                app.score.player1, app.score.player2
+            ))])
+            .style(Style::default().fg(Color::Yellow))
+            .height(2),
+        );
+    }
+    rows.push(
+        if let (GameState::GameInProgress(..), Some((seat, elapsed))) = (game_state, thinking) {
+            let frame = (elapsed.as_millis() / 250) as usize % SPINNER.len();
+            Row::new(vec![Cell::from(format!(
+                "{} {} is thinking…",
+                SPINNER[frame],
+                seat.name()
+            ))])
+            .style(Style::default().fg(get_color(seat.mark.player())))
+        } else if let GameState::GameInProgress(_, player, _) = game_state {
             Row::new(vec![Cell::from(format!("{}'s turn", player,))])
                 .style(Style::default().fg(get_color(*player)))
         } else {
             Row::new(vec![Cell::from("Game Over".to_string())])
                 .style(Style::default().fg(Color::Red))
         },
-    ])
-    .block(Block::default().borders(Borders::ALL))
-    .widths(&[Constraint::Percentage(100)]);
+    );
+    if let Some(difficulty) = session.adaptive() {
+        rows.push(Row::new(vec![Cell::from(format!(
+            "Adaptive level: {}/10 ({:.0}% random moves)",
+            difficulty.level(),
+            difficulty.error_rate() * 100.0
+        ))]));
+    }
+    if let Some(remote) = &session.remote {
+        let turn = match game_state {
+            GameState::GameInProgress(_, player, _) if Mark::of(*player) != remote.mark => {
+                format!("\nWaiting for {}…", remote.opponent)
+            }
+            _ => String::new(),
+        };
+        rows.push(
+            Row::new(vec![Cell::from(format!(
+                "Room {}: you play {} against {}{}",
+                remote.room, remote.mark, remote.opponent, turn
+            ))])
+            .height(2),
+        );
+    }
+    let table = Table::new(rows)
+        .block(Block::default().borders(Borders::ALL))
+        .widths(&[Constraint::Percentage(100)]);
 
     f.render_widget(table, *rect)
 }
 
-fn draw_game_over<B: Backend>(f: &mut Frame<B>, rect: &Rect, winner: Option<Player>, cells: Cells) {
+// The players of the watched game with their clocks, whose turn it is and the moves so far
+fn draw_watch<B: Backend>(f: &mut Frame<B>, session: &Session, watch: &Watch, rect: &Rect) {
+    let mut rows = vec![Row::new(vec![Cell::from(format!(
+        "Watching room {}",
+        watch.room
+    ))])];
+    for (mark, name) in [Mark::X, Mark::O].into_iter().zip(&watch.players) {
+        let name = if name.is_empty() {
+            "(empty seat)"
+        } else {
+            name
+        };
+        rows.push(
+            Row::new(vec![Cell::from(format!(
+                "{}: {} {}",
+                mark,
+                name,
+                clock(watch.clock(mark))
+            ))])
+            .style(Style::default().fg(get_color(mark.player()))),
+        );
+    }
+    rows.push(if watch.over {
+        Row::new(vec![Cell::from("Game Over")]).style(Style::default().fg(Color::Red))
+    } else {
+        Row::new(vec![Cell::from(format!("{} to move", watch.to_move))])
+            .style(Style::default().fg(get_color(watch.to_move.player())))
+    });
+    let moves = session.history.game(&session.app).movetext();
+    rows.push(Row::new(vec![Cell::from(moves)]).height(2));
+    let table = Table::new(rows)
+        .block(Block::default().borders(Borders::ALL))
+        .widths(&[Constraint::Percentage(100)]);
+    f.render_widget(table, *rect)
+}
+
+// Minutes and seconds, like a chess clock
+fn clock(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn draw_game_over<B: Backend>(
+    f: &mut Frame<B>,
+    rect: &Rect,
+    winner: Option<Player>,
+    cells: Cells,
+    style: Style,
+) {
     let mut rows = cells
         .iter()
         .map(|item| {
@@ -142,7 +361,8 @@ fn draw_game_over<B: Backend>(f: &mut Frame<B>, rect: &Rect, winner: Option<Play
                 } else {
                     Color::Gray
                 })
-                .add_modifier(Modifier::BOLD),
+                .add_modifier(Modifier::BOLD)
+                .patch(style),
         );
     f.render_widget(t, *rect);
     // let block = Paragraph::new(format!("Game over! \n{}", winning_message))
@@ -166,46 +386,368 @@ fn draw_warning<B: Backend>(f: &mut Frame<B>, rect: &Rect, message: String) {
     f.render_widget(block, *rect);
 }
 
-fn draw_board<B: Backend>(f: &mut Frame<B>, cells: Cells, pos: Position, rect: &Rect) {
+fn draw_status<B: Backend>(f: &mut Frame<B>, rect: &Rect, status: &str) {
+    let block = Paragraph::new(status)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("Info").borders(Borders::ALL));
+    f.render_widget(block, *rect);
+}
+
+fn draw_board<B: Backend>(
+    f: &mut Frame<B>,
+    board: &Board,
+    cursor: Option<(usize, usize)>,
+    hints: &[((usize, usize), Value)],
+    rect: &Rect,
+    style: Style,
+) {
     // TODO: Make it look like a tic tac toe board
     let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-    let rows = cells.iter().enumerate().map(|(i, item)| {
+    let rows = board.rows().enumerate().map(|(i, item)| {
         // let height = item
         //     .iter()
         //     .map(|content| content.to_text(None).chars().filter(|c| *c == '\n').count())
         //     .max()
         //     .unwrap_or(0)
         //     + 1;
-        let cells = item.iter().enumerate().map(|(j, c)| {
-            Cell::from(Span::raw(c.to_text(Some((i, j))))).style(if (i, j) == pos.to_tuple() {
+        let cells = item.iter().enumerate().map(|(j, mark)| {
+            let hint = hints.iter().find(|(cell, _)| *cell == (i, j));
+            let (text, color) = match hint {
+                Some((_, value)) => (value.to_string(), get_value_color(*value)),
+                None => (
+                    game_cell(*mark).to_text(Some((i, j))),
+                    get_mark_color(*mark),
+                ),
+            };
+            Cell::from(Span::raw(text)).style(if Some((i, j)) == cursor {
                 selected_style
             } else {
-                Style::default().fg(match c {
-                    GameCell::Empty => Color::Gray,
-                    GameCell::Cross => Color::Red,
-                    GameCell::Circle => Color::Blue,
-                })
+                Style::default().fg(color)
             })
         });
         Row::new(cells).height(rect.height / 3)
     });
     let t = Table::new(rows)
         .block(Block::default().borders(Borders::ALL))
+        .style(style)
         .widths(&[Constraint::Ratio(1, 3); 3]);
     f.render_widget(t, *rect)
 }
 
-fn draw_info<B: Backend>(f: &mut Frame<B>, rect: &Rect, state: &GameState) {
-    let info = match state {
+fn draw_info<B: Backend>(f: &mut Frame<B>, rect: &Rect, state: &GameState, seat: Option<&Seat>) {
+    let mut info = match state {
         GameState::GameInProgress(_, _, _) => "Game in progress...\nPress M/ Esc to open the Game Menu\nPress P to place a piece, Q to \
             quit, or R to reset the board.\nUse the arrow keys to move the piece.".to_string(),
         GameState::GameOver(..) => "Game over!\nPress M/ Esc to open the Game Menu\nPress R to reset the board or Q to quit."
             .to_string(),
-        // TODO:: Add Menu info
-        // GameState::Menu(_) => "Tic Tac Toe Menu\nPress Q to quit, or use the up and down arrow keys to select an item."
-        //     .to_string(),
     };
-    let text_block =
-        Paragraph::new(info).block(Block::default().title("Info").borders(Borders::ALL));
+    if let Some((seat, remark)) = seat.and_then(|seat| Some((seat, seat.remark()?))) {
+        info = format!("{}: \"{}\"\n{}", seat.name(), remark, info);
+    }
+    let text_block = Paragraph::new(info).block(
+        Block::default()
+            .title("Info (press ? for help)")
+            .borders(Borders::ALL),
+    );
     f.render_widget(text_block, *rect);
 }
+
+fn draw_watch_info<B: Backend>(f: &mut Frame<B>, rect: &Rect) {
+    let info = "Watching a game on the lobby server\nPress L to stop watching and go back to the \
+        lobby, M/ Esc to open the Game Menu or Q to quit.";
+    let text_block = Paragraph::new(info).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .title("Info (press ? for help)")
+            .borders(Borders::ALL),
+    );
+    f.render_widget(text_block, *rect);
+}
+
+// Takes the place of the info panel during a network game, with the latest messages at
+// the bottom above any warning and the message being typed
+fn draw_chat<B: Backend>(f: &mut Frame<B>, rect: &Rect, chat: &Chat, warning: Option<&str>) {
+    let mut footer = Vec::new();
+    if let Some(warning) = warning {
+        let style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+        footer.push(Spans::from(Span::styled(warning, style)));
+    }
+    if let Some(input) = &chat.input {
+        footer.push(Spans::from(format!("> {}_", input)));
+    }
+    let room = (rect.height as usize).saturating_sub(2 + footer.len());
+    let end = chat.messages.len().saturating_sub(chat.scroll);
+    let mut lines: Vec<Spans> = chat.messages[end.saturating_sub(room)..end]
+        .iter()
+        .map(|(from, text)| match from {
+            Some(from) => Spans::from(vec![
+                Span::styled(
+                    format!("{}: ", from),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(text.as_str()),
+            ]),
+            None => Spans::from(Span::styled(
+                text.as_str(),
+                Style::default().add_modifier(Modifier::ITALIC),
+            )),
+        })
+        .collect();
+    lines.extend(footer);
+    let title = match chat.input {
+        Some(_) => "Chat (Enter to send, Esc to stop typing)",
+        None => "Chat (/ or Tab to type, 1-4 quick messages, Up / Down to scroll while waiting)",
+    };
+    let block = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(block, *rect);
+}
+
+fn draw_editor<B: Backend>(f: &mut Frame<B>, editor: &Editor, main: &[Rect], info: &Rect) {
+    draw_board(
+        f,
+        &editor.board,
+        Some(editor.cursor),
+        &[],
+        &main[0],
+        Style::default(),
+    );
+
+    let mut rows = vec![
+        Row::new(vec![Cell::from("Board editor")]),
+        Row::new(vec![Cell::from(format!("Position: {}", editor.board))]),
+        // X always moves first, so the pieces on the board decide whose turn it is
+        Row::new(vec![Cell::from(format!("{} to move", editor.board.turn()))])
+            .style(Style::default().fg(get_color(editor.board.turn().player()))),
+    ];
+    if let Some(error) = &editor.error {
+        rows.push(
+            Row::new(vec![Cell::from(error.as_str())])
+                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        );
+    }
+    let table = Table::new(rows)
+        .block(Block::default().borders(Borders::ALL))
+        .widths(&[Constraint::Percentage(100)]);
+    f.render_widget(table, main[1]);
+
+    let text_block = Paragraph::new(
+        "Use the arrow keys to move, X or O to place a piece and Space to clear a cell.\n\
+        Press Enter to start playing or Esc to go back.",
+    )
+    .wrap(Wrap { trim: true })
+    .block(
+        Block::default()
+            .title("Info (press ? for help)")
+            .borders(Borders::ALL),
+    );
+    f.render_widget(text_block, *info);
+}
+
+fn draw_lobby<B: Backend>(f: &mut Frame<B>, lobby: &Lobby, main: &[Rect], info: &Rect) {
+    let rooms = lobby.rooms.iter().map(|room| {
+        let state = if room.open { "open" } else { "full" };
+        let style = if room.open {
+            Style::default()
+        } else {
+            Style::default().add_modifier(Modifier::DIM)
+        };
+        Row::new([
+            Cell::from(format!("Room {}", room.id)),
+            Cell::from(state),
+            Cell::from(room.players.as_str()),
+        ])
+        .style(style)
+    });
+    let table = Table::new(rooms)
+        .block(Block::default().title("Rooms").borders(Borders::ALL))
+        .highlight_style(Style::default().fg(Color::Yellow))
+        .highlight_symbol(">>")
+        .widths(&[
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+            Constraint::Percentage(60),
+        ]);
+    let mut table_state = TableState::default();
+    table_state.select((!lobby.rooms.is_empty()).then_some(lobby.selected));
+    f.render_stateful_widget(table, main[0], &mut table_state);
+
+    let mut rows = vec![Row::new(vec![Cell::from("Lobby")])];
+    rows.push(Row::new(vec![Cell::from(match lobby.waiting {
+        Some(room) => format!("Waiting for an opponent in room {}…", room),
+        None => "Create a room or join an open one".to_string(),
+    })]));
+    if let Some(message) = &lobby.message {
+        rows.push(
+            Row::new(vec![Cell::from(message.as_str())])
+                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        );
+    }
+    let table = Table::new(rows)
+        .block(Block::default().borders(Borders::ALL))
+        .widths(&[Constraint::Percentage(100)]);
+    f.render_widget(table, main[1]);
+
+    let help = match lobby.waiting {
+        Some(_) => "Press Esc to leave the room, R to refresh the list or Q to quit.",
+        None => {
+            "Use Up and Down to pick a room, Enter to join it or W to watch its game.\n\
+            Press C to create a room, R to refresh the list or Q to quit."
+        }
+    };
+    let text_block = Paragraph::new(help).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .title("Info (press ? for help)")
+            .borders(Borders::ALL),
+    );
+    f.render_widget(text_block, *info);
+}
+
+fn help_bindings(session: &Session) -> Vec<(&'static str, &'static str)> {
+    let mut bindings = match &session.app.state {
+        _ if session.editor.is_some() => vec![
+            ("Arrow keys", "Move the cursor"),
+            ("X / O", "Put a cross or a circle on the selected cell"),
+            ("Space / .", "Clear the selected cell"),
+            ("Enter", "Check the position and start playing from it"),
+            ("Esc", "Back to the start menu"),
+        ],
+        _ if session.lobby.is_some() => vec![
+            ("Up / Down", "Select a room"),
+            ("Enter", "Join the selected room"),
+            ("W", "Watch the game in the selected room"),
+            ("C", "Create a room and wait for an opponent"),
+            ("R", "Refresh the list of rooms"),
+            ("Esc", "Leave the room you are waiting in"),
+            ("Q", "Quit"),
+        ],
+        AppState::Playing(_) if session.watch.is_some() => vec![
+            ("L", "Stop watching and go back to the lobby"),
+            ("M / Esc", "Open the game menu"),
+            ("Q", "Quit"),
+        ],
+        AppState::Playing(GameState::GameInProgress(..)) if session.remote.is_some() => vec![
+            ("Arrow keys", "Move the cursor"),
+            ("P", "Place a piece on the selected cell"),
+            ("L", "Leave the room and go back to the lobby"),
+            ("/ or Tab", "Type a chat message"),
+            ("Up / Down", "Scroll the chat while typing or waiting"),
+            ("1-4", "Send gg, nice move, good luck or well played"),
+            ("M / Esc", "Open the game menu"),
+            ("Q", "Quit"),
+        ],
+        AppState::Playing(GameState::GameOver(..)) if session.remote.is_some() => vec![
+            ("R", "Play again once your opponent agrees"),
+            ("L", "Leave the room and go back to the lobby"),
+            ("/ or Tab", "Type a chat message"),
+            ("Up / Down", "Scroll the chat while typing or waiting"),
+            ("1-4", "Send gg, nice move, good luck or well played"),
+            ("M / Esc", "Open the game menu"),
+            ("Q", "Quit"),
+        ],
+        AppState::StartMenu(_) => vec![
+            ("Up / Down", "Select an opponent"),
+            ("Enter", "Start the game"),
+            ("E", "Set up a starting position"),
+            ("Q", "Quit"),
+        ],
+        AppState::Playing(GameState::GameInProgress(..)) => vec![
+            ("Arrow keys", "Move the cursor"),
+            ("P", "Place a piece on the selected cell"),
+            ("R", "Reset the board"),
+            ("M / Esc", "Open the game menu"),
+            ("Q", "Quit"),
+        ],
+        AppState::Playing(GameState::GameOver(..)) => vec![
+            ("R", "Reset the board and play again"),
+            ("M / Esc", "Open the game menu"),
+            ("Q", "Quit"),
+        ],
+        AppState::GameMenu(_) => vec![
+            ("Up / Down", "Select a menu item"),
+            ("Enter", "Resume, start a new game or quit"),
+            ("E", "Export the game to a file"),
+            ("A", "Show or hide the outcome of every move"),
+        ],
+        _ => vec![],
+    };
+    bindings.push(("?", "Show or hide this help"));
+    bindings
+}
+
+fn draw_help<B: Backend>(
+    f: &mut Frame<B>,
+    bindings: Vec<(&'static str, &'static str)>,
+    scroll: &mut u16,
+) {
+    let area = centered_rect(60, 60, f.size());
+    let lines = bindings
+        .into_iter()
+        .map(|(key, action)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:<12}", key),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(action),
+            ])
+        })
+        .collect::<Vec<_>>();
+    // keep the last line of the help text at the bottom of the popup
+    let max_scroll = (lines.len() as u16).saturating_sub(area.height.saturating_sub(2));
+    *scroll = (*scroll).min(max_scroll);
+    let help = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Help (Up/Down to scroll, Esc to close)")
+                .borders(Borders::ALL),
+        )
+        .scroll((*scroll, 0));
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+fn draw_confirm<B: Backend>(f: &mut Frame<B>, question: &str) {
+    let area = centered_rect(50, 30, f.size());
+    let dialog = Paragraph::new(vec![
+        Spans::from(question),
+        Spans::from(""),
+        Spans::from(Span::styled(
+            "y/N",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .block(Block::default().title("Confirm").borders(Borders::ALL))
+    .style(Style::default().fg(Color::Red));
+    f.render_widget(Clear, area);
+    f.render_widget(dialog, area);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, rect: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(rect);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}